@@ -0,0 +1,365 @@
+//! Log viewer built on the `Logs` widget, for log files, the standard input
+//! or forwarded events. Run with `--help` for its options.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
+
+use egui_tracing::parse::{reader_for_format, LogReader, FORMATS};
+use egui_tracing::remote::EventReceiver;
+use egui_tracing::tracing::CollectedEvent;
+use egui_tracing::{EventCollector, Logs};
+
+const USAGE: &str = "\
+Usage: egui_tracing [OPTIONS] [FILE]...
+
+Shows log files, or the standard input if no file, `-` or `--listen` is
+given.
+
+Options:
+      --format <FORMAT>  Format of the logs: json, fmt, logfmt or env_logger
+                         [default: json]
+  -f, --follow           Watch the files for appended lines
+      --listen <ADDR>    Receive events forwarded by other processes, e.g.
+                         `0.0.0.0:7070`
+  -h, --help             Print this help
+";
+
+/// Events are added to the collector in batches of this size while reading.
+const BATCH_SIZE: usize = 1024;
+
+/// How often followed files are checked for new lines.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long to wait for continuation lines of a multi-line event before
+/// showing it, while the input is still being written.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+struct Args {
+    format: String,
+    follow: bool,
+    listen: Option<String>,
+    /// `None` for the standard input.
+    inputs: Vec<Option<PathBuf>>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        format: "json".to_owned(),
+        follow: false,
+        listen: None,
+        inputs: Vec::new(),
+    };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-f" | "--follow" => args.follow = true,
+            "--format" => {
+                args.format = iter.next().ok_or("missing value for `--format`")?;
+            }
+            "--listen" => {
+                args.listen = Some(iter.next().ok_or("missing value for `--listen`")?);
+            }
+            "-" => args.inputs.push(None),
+            arg if arg.starts_with('-') => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            path => args.inputs.push(Some(PathBuf::from(path))),
+        }
+    }
+    if !FORMATS.contains(&args.format.as_str()) {
+        return Err(format!(
+            "unknown format `{}`, expected one of: {}",
+            args.format,
+            FORMATS.join(", ")
+        ));
+    }
+    if args.inputs.is_empty() && args.listen.is_none() {
+        args.inputs.push(None);
+    }
+    Ok(args)
+}
+
+fn main() -> eframe::Result {
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("error: {}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+
+    let collector = EventCollector::default();
+    let receiver = args.listen.as_ref().map(|addr| {
+        EventReceiver::bind(addr, &collector).unwrap_or_else(|err| {
+            eprintln!("error: failed to listen on {}: {}", addr, err);
+            process::exit(1);
+        })
+    });
+
+    let mut names: Vec<_> = args
+        .inputs
+        .iter()
+        .map(|input| match input {
+            Some(path) => path.display().to_string(),
+            None => "stdin".to_owned(),
+        })
+        .collect();
+    if let Some(receiver) = &receiver {
+        names.push(format!("listening on {}", receiver.local_addr()));
+    }
+    let title = format!("egui_tracing - {}", names.join(", "));
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title(&title)
+            .with_inner_size([1000.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "egui_tracing",
+        options,
+        Box::new(move |cc| {
+            let ctx = cc.egui_ctx.clone();
+            Ok(Box::new(Viewer::new(args, collector, receiver, names, ctx)))
+        }),
+    )
+}
+
+struct Viewer {
+    collector: EventCollector,
+    receiver: Option<EventReceiver>,
+    names: Vec<String>,
+    follow: Arc<AtomicBool>,
+    invalid_lines: Arc<AtomicUsize>,
+    /// Whether any input is a file that can be followed.
+    followable: bool,
+}
+
+impl Viewer {
+    fn new(
+        args: Args,
+        collector: EventCollector,
+        receiver: Option<EventReceiver>,
+        names: Vec<String>,
+        ctx: egui::Context,
+    ) -> Self {
+        let follow = Arc::new(AtomicBool::new(args.follow));
+        let invalid_lines = Arc::new(AtomicUsize::new(0));
+        let followable = args.inputs.iter().any(Option::is_some);
+
+        for (input, name) in args.inputs.into_iter().zip(&names) {
+            let mut importer = Importer {
+                reader: reader_for_format(&args.format, &collector).unwrap(),
+                source: name.clone(),
+                collector: collector.clone(),
+                batch: Vec::new(),
+                invalid_lines: invalid_lines.clone(),
+                ctx: ctx.clone(),
+            };
+            let follow = follow.clone();
+            let name = name.clone();
+            thread::spawn(move || {
+                let result = match input {
+                    Some(path) => importer.read_file(&path, &follow),
+                    None => importer.read_stdin(),
+                };
+                if let Err(err) = result {
+                    eprintln!("error: failed to read {}: {}", name, err);
+                }
+            });
+        }
+
+        Self {
+            collector,
+            receiver,
+            names,
+            follow,
+            invalid_lines,
+            followable,
+        }
+    }
+}
+
+impl eframe::App for Viewer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("inputs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(self.names.join(", "));
+                ui.separator();
+                let mut follow = self.follow.load(Ordering::Relaxed);
+                let checkbox = egui::Checkbox::new(&mut follow, "Follow");
+                if ui
+                    .add_enabled(self.followable, checkbox)
+                    .on_hover_text("Watch the files for appended lines")
+                    .changed()
+                {
+                    self.follow.store(follow, Ordering::Relaxed);
+                }
+                let invalid_lines = self.invalid_lines.load(Ordering::Relaxed);
+                if invalid_lines > 0 {
+                    ui.weak(format!("{} lines skipped", invalid_lines))
+                        .on_hover_text("Lines that could not be parsed");
+                }
+                if let Some(receiver) = &self.receiver {
+                    for source in receiver.sources() {
+                        ui.separator();
+                        let label = if source.connected {
+                            egui::RichText::new(&source.label)
+                        } else {
+                            egui::RichText::new(&source.label).weak()
+                        };
+                        ui.label(label).on_hover_text(format!(
+                            "{}, {}\n{} events, {} connections",
+                            source.addr,
+                            if source.connected { "connected" } else { "disconnected" },
+                            source.events,
+                            source.connections,
+                        ));
+                    }
+                }
+            });
+        });
+        if self.receiver.is_some() {
+            // forwarded events don't wake up the UI.
+            ctx.request_repaint_after(POLL_INTERVAL);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.add(Logs::new(self.collector.clone()).id_source("viewer"));
+        });
+    }
+}
+
+/// Reads an input on its own thread and adds its events to the collector,
+/// with the name of the input as the source of events not naming one.
+struct Importer {
+    reader: Box<dyn LogReader>,
+    source: String,
+    collector: EventCollector,
+    batch: Vec<CollectedEvent>,
+    invalid_lines: Arc<AtomicUsize>,
+    ctx: egui::Context,
+}
+
+impl Importer {
+    fn read_stdin(&mut self) -> io::Result<()> {
+        // read on another thread, so that waiting for input can time out.
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            let line = match lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => {
+                    // no more input for now, show what was read.
+                    self.commit();
+                    match lines.recv_timeout(IDLE_TIMEOUT) {
+                        Ok(line) => line,
+                        Err(RecvTimeoutError::Timeout) => {
+                            self.flush();
+                            let Ok(line) = lines.recv() else { break };
+                            line
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
+            self.line(&line?);
+        }
+        self.flush();
+        Ok(())
+    }
+
+    /// Reads the file to its end, then keeps polling it for appended lines
+    /// while following is enabled.
+    fn read_file(&mut self, path: &Path, follow: &AtomicBool) -> io::Result<()> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut position = 0;
+        let mut line = String::new();
+        let mut last_read = Instant::now();
+        loop {
+            let read = file.read_line(&mut line)?;
+            position += read as u64;
+            if read > 0 {
+                last_read = Instant::now();
+            }
+            if line.ends_with('\n') {
+                self.line(&line);
+                line.clear();
+                continue;
+            }
+
+            // at the end of the file, with a partial line if still written.
+            let following = follow.load(Ordering::Relaxed);
+            if !following && !line.is_empty() {
+                self.line(&line);
+                line.clear();
+            }
+            if !following || last_read.elapsed() >= IDLE_TIMEOUT {
+                self.flush();
+            } else {
+                // the next lines may still continue the last event.
+                self.commit();
+            }
+            while !follow.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+            }
+            if read == 0 {
+                thread::sleep(POLL_INTERVAL);
+            }
+            if file.get_ref().metadata()?.len() < position {
+                // truncated, e.g. by log rotation.
+                file.seek(SeekFrom::Start(0))?;
+                position = 0;
+                line.clear();
+            }
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        match self.reader.read_line(line.trim_end_matches(['\r', '\n'])) {
+            Ok(Some(event)) => self.push(event),
+            Ok(None) => {}
+            Err(_) => {
+                self.invalid_lines.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if self.batch.len() >= BATCH_SIZE {
+            self.commit();
+        }
+    }
+
+    fn push(&mut self, mut event: CollectedEvent) {
+        event.source.get_or_insert_with(|| self.source.clone());
+        self.batch.push(event);
+    }
+
+    /// Adds the events read so far to the collector.
+    fn commit(&mut self) {
+        if !self.batch.is_empty() {
+            self.collector.extend(self.batch.drain(..));
+            self.ctx.request_repaint();
+        }
+    }
+
+    /// Also ends the event still being read.
+    fn flush(&mut self) {
+        if let Some(event) = self.reader.flush() {
+            self.push(event);
+        }
+        self.commit();
+    }
+}
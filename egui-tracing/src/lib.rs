@@ -1,5 +1,7 @@
 #![warn(clippy::all, clippy::cargo)]
 
+pub mod parse;
+pub mod remote;
 mod string;
 mod time;
 pub mod tracing;
@@ -11,4 +13,4 @@ pub use egui;
 pub use tracing_subscriber;
 
 pub use self::tracing::EventCollector;
-pub use self::ui::Logs;
+pub use self::ui::{Logs, LogsExporter, LogsStorage, SpanTimeline};
@@ -0,0 +1,118 @@
+use super::text::{new_event, parse_level, parse_timestamp, strip_ansi, MultiLine};
+use super::{LogReader, ParseLineError};
+use crate::tracing::{CollectedEvent, FieldValue};
+
+/// Parses the default format of `env_logger`, with or without colors, e.g.
+///
+/// ```text
+/// [2024-05-01T12:00:00Z INFO  my_app::net] connected to 10.0.0.1
+/// ```
+///
+/// The timestamp and target are optional. Lines that don't start with such a
+/// header continue the previous message.
+#[derive(Debug, Default)]
+pub struct EnvLoggerReader {
+    lines: MultiLine,
+}
+
+impl EnvLoggerReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn parse(&mut self, line: &str) -> Option<CollectedEvent> {
+        let (header, message) = line.strip_prefix('[')?.split_once(']')?;
+        let words: Vec<_> = header.split_whitespace().collect();
+        let (time, level, target) = match words[..] {
+            [time, level, target] => (parse_timestamp(time), level, target),
+            [first, second] => match parse_timestamp(first) {
+                Some(time) => (Some(time), second, ""),
+                None => (None, first, second),
+            },
+            [level] => (None, level, ""),
+            _ => return None,
+        };
+
+        let time = match time {
+            Some(time) => Some(time),
+            None if words.len() == 3 => return None,
+            None => None,
+        };
+        let mut event = new_event(parse_level(level)?, target.to_owned(), self.lines.time(time));
+        let message = message.strip_prefix(' ').unwrap_or(message);
+        event
+            .fields
+            .insert("message".to_owned(), FieldValue::Str(message.to_owned()));
+        Some(event)
+    }
+}
+
+impl LogReader for EnvLoggerReader {
+    fn read_line(&mut self, line: &str) -> Result<Option<CollectedEvent>, ParseLineError> {
+        let line = strip_ansi(line);
+        let event = self.parse(&line);
+        self.lines.read(event, &line)
+    }
+
+    fn flush(&mut self) -> Option<CollectedEvent> {
+        self.lines.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use tracing::Level;
+
+    use super::*;
+
+    fn message(event: &CollectedEvent) -> &str {
+        event.fields["message"].as_str().unwrap()
+    }
+
+    #[test]
+    fn parses_line() {
+        let mut reader = EnvLoggerReader::new();
+        let line = "[2024-05-01T12:00:00Z INFO  my_app::net] connected to \"10.0.0.1\"";
+        assert!(reader.read_line(line).unwrap().is_none());
+        let event = reader.flush().unwrap();
+
+        assert_eq!(event.time, Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        assert_eq!(event.level, Level::INFO);
+        assert_eq!(event.target, "my_app::net");
+        // quotes are part of the message, as env_logger doesn't escape.
+        assert_eq!(message(&event), "connected to \"10.0.0.1\"");
+    }
+
+    #[test]
+    fn parses_optional_parts_and_colors() {
+        let mut reader = EnvLoggerReader::new();
+        reader.read_line("[WARN my_app] no time").unwrap();
+        let event = reader.read_line("\x1b[1m[ERROR]\x1b[0m bare").unwrap().unwrap();
+        assert_eq!((event.level, event.target.as_str()), (Level::WARN, "my_app"));
+        assert_eq!(message(&event), "no time");
+
+        let event = reader.flush().unwrap();
+        assert_eq!((event.level, event.target.as_str()), (Level::ERROR, ""));
+        assert_eq!(message(&event), "bare");
+    }
+
+    #[test]
+    fn appends_continuation_lines() {
+        let mut reader = EnvLoggerReader::new();
+        reader.read_line("[DEBUG x] first").unwrap();
+        assert!(reader.read_line("second").unwrap().is_none());
+        assert!(reader.read_line("[not a header").unwrap().is_none());
+        let event = reader.read_line("[INFO x] next").unwrap().unwrap();
+        assert_eq!(message(&event), "first\nsecond\n[not a header");
+    }
+
+    #[test]
+    fn rejects_lines_before_the_first_event() {
+        let mut reader = EnvLoggerReader::new();
+        assert!(reader.read_line("plain text").is_err());
+        assert!(reader.read_line("[LOUD x] unknown level").is_err());
+        assert!(reader.read_line("[not-a-time INFO x] three words").is_err());
+        assert!(reader.flush().is_none());
+    }
+}
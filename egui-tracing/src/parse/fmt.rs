@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use tracing::Level;
+
+use super::text::{
+    new_event,
+    parse_level,
+    parse_pairs,
+    parse_timestamp,
+    split_pairs,
+    strip_ansi,
+    MultiLine,
+};
+use super::{LogReader, ParseLineError, SpanIds};
+use crate::tracing::{CollectedEvent, EventCollector, FieldValue, SpanInfo};
+
+/// Parses the text written by `tracing_subscriber::fmt()` in its full and
+/// compact formats, with or without colors, e.g.
+///
+/// ```text
+/// 2024-05-01T12:00:00.000000Z  INFO request{id=3}: my_app::net: sent bytes=512
+/// ```
+///
+/// The timestamp, spans and target are optional, as are the file and line
+/// after the target. Thread names are not recognized. Lines that don't start
+/// with a level, after the timestamp, continue the previous message.
+///
+/// Span fields are parsed from the full format. Like in the JSON format,
+/// spans with the same name and fields below the same parents are taken to
+/// be the same span, see [`JsonReader`](super::JsonReader).
+#[derive(Debug, Default)]
+pub struct FmtReader {
+    spans: SpanIds,
+    lines: MultiLine,
+}
+
+impl FmtReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates span ids like `collector`, see
+    /// [`JsonReader::for_collector`](super::JsonReader::for_collector).
+    pub fn for_collector(collector: &EventCollector) -> Self {
+        Self {
+            spans: SpanIds::for_collector(collector),
+            lines: MultiLine::default(),
+        }
+    }
+
+    fn parse(&mut self, line: &str) -> Option<CollectedEvent> {
+        let mut rest = line.trim_start();
+        let (first, after) = split_word(rest);
+        let time = parse_timestamp(first);
+        if time.is_some() {
+            rest = after;
+        }
+
+        // levels are padded to the same width.
+        let (level, after) = split_word(rest);
+        if level.chars().any(|c| c.is_ascii_lowercase()) {
+            return None;
+        }
+        let level = parse_level(level)?;
+        rest = after;
+
+        let mut thread_id = None;
+        let (word, after) = split_word(rest);
+        if word.starts_with("ThreadId(") {
+            thread_id = Some(word.to_owned());
+            rest = after;
+        }
+
+        let (spans, target, mut text) = match take_prefix(rest) {
+            Some((first, after)) => match take_prefix(after) {
+                Some((second, after)) if is_spans(first, second) && is_target(second) => {
+                    (first, second, after)
+                }
+                _ if is_target(first) => ("", first, after),
+                _ => ("", "", rest),
+            },
+            None => ("", "", rest),
+        };
+
+        let mut event = new_event(level, target.to_owned(), self.lines.time(time));
+        event.thread_id = thread_id;
+        event.spans = self.spans(spans, target, level);
+        if let Some((location, after)) = take_prefix(text) {
+            if let Some((file, line)) = location.rsplit_once(':') {
+                if let Ok(line) = line.parse() {
+                    event.file = Some(file.to_owned());
+                    event.line = Some(line);
+                    text = after;
+                }
+            }
+        }
+
+        let (message, pairs) = split_pairs(text);
+        if !message.is_empty() {
+            let message = FieldValue::Str(message.to_owned());
+            event.fields.insert("message".to_owned(), message);
+        }
+        for (key, value) in pairs {
+            event
+                .fields
+                .insert(key.to_owned(), value.into_field(FieldValue::Debug));
+        }
+        Some(event)
+    }
+
+    /// Parses a span list like `outer{id=3}:inner`.
+    fn spans(&mut self, text: &str, target: &str, level: Level) -> Vec<Arc<SpanInfo>> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        for end in split_points(text, ':') {
+            let span = &text[start..end];
+            start = end + 1;
+            if span.is_empty() {
+                continue;
+            }
+            let (name, fields) = match span.split_once('{') {
+                Some((name, fields)) => (name, fields.strip_suffix('}').unwrap_or(fields)),
+                None => (span, ""),
+            };
+            let span = self.spans.get(&text[..end], |id| SpanInfo {
+                id,
+                name: name.to_owned(),
+                target: target.to_owned(),
+                level,
+                fields: parse_pairs(fields)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(key, value)| (key.to_owned(), value.into_field(FieldValue::Debug)))
+                    .collect(),
+            });
+            spans.push(span);
+        }
+        spans
+    }
+}
+
+impl LogReader for FmtReader {
+    fn read_line(&mut self, line: &str) -> Result<Option<CollectedEvent>, ParseLineError> {
+        let line = strip_ansi(line);
+        let event = self.parse(&line);
+        self.lines.read(event, &line)
+    }
+
+    fn flush(&mut self) -> Option<CollectedEvent> {
+        self.lines.flush()
+    }
+}
+
+/// Splits off the first word and the whitespace after it.
+fn split_word(text: &str) -> (&str, &str) {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (&text[..end], text[end..].trim_start())
+}
+
+/// Positions of `separator` outside of braces and quotes, followed by the end
+/// of `text`.
+fn split_points(text: &str, separator: char) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '{' if !quoted => depth += 1,
+            '}' if !quoted => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 && !quoted => points.push(index),
+            _ => {}
+        }
+    }
+    points.push(text.len());
+    points
+}
+
+/// Splits `text` at the first `: ` outside of braces and quotes, if there is
+/// no space before it, e.g. after a target or a span list.
+fn take_prefix(text: &str) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '{' if !quoted => depth += 1,
+            '}' if !quoted => depth = depth.saturating_sub(1),
+            _ if quoted || depth > 0 => {}
+            ' ' => return None,
+            ':' if text[index + 1..].starts_with(' ') || index + 1 == text.len() => {
+                return Some((&text[..index], text[index + 1..].trim_start()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_target(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'))
+}
+
+/// Whether `first` is a span list followed by the target `second`, rather
+/// than a target followed by a message starting with `second: `.
+fn is_spans(first: &str, second: &str) -> bool {
+    first.contains('{')
+        || first.replace("::", "").contains(':')
+        || (second.contains("::") && !first.contains("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn message(event: &CollectedEvent) -> &str {
+        event.fields["message"].as_str().unwrap()
+    }
+
+    #[test]
+    fn parses_full_format() {
+        let mut reader = FmtReader::new();
+        let line = "2024-05-01T12:00:00.000000Z  INFO request{id=3}:db: my_app::net: \
+                    sent bytes=512 ok=true";
+        assert!(reader.read_line(line).unwrap().is_none());
+        let event = reader.flush().unwrap();
+
+        assert_eq!(event.time, Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        assert_eq!(event.level, Level::INFO);
+        assert_eq!(event.target, "my_app::net");
+        assert_eq!(message(&event), "sent");
+        assert_eq!(event.fields["bytes"], FieldValue::I64(512));
+        assert_eq!(event.fields["ok"], FieldValue::Bool(true));
+        let spans: Vec<_> = event.spans.iter().map(|span| span.name.as_str()).collect();
+        assert_eq!(spans, ["request", "db"]);
+        assert_eq!(event.spans[0].fields["id"], FieldValue::I64(3));
+    }
+
+    #[test]
+    fn parses_colors_location_and_thread() {
+        let mut reader = FmtReader::new();
+        let line = "\x1b[2m2024-05-01T12:00:00Z\x1b[0m \x1b[31mERROR\x1b[0m ThreadId(02) \
+                    my_app: src/main.rs:42: failed";
+        reader.read_line(line).unwrap();
+        let event = reader.flush().unwrap();
+        assert_eq!(event.level, Level::ERROR);
+        assert_eq!(event.thread_id.as_deref(), Some("ThreadId(02)"));
+        assert_eq!(event.file.as_deref(), Some("src/main.rs"));
+        assert_eq!(event.line, Some(42));
+        assert_eq!(message(&event), "failed");
+    }
+
+    #[test]
+    fn keeps_spans_with_the_same_path() {
+        let mut reader = FmtReader::new();
+        reader.read_line(" INFO a{id=1}: app: one").unwrap();
+        let first = reader.read_line(" INFO a{id=1}: app: two").unwrap().unwrap();
+        let second = reader.read_line(" INFO a{id=2}: app: three").unwrap().unwrap();
+        let third = reader.flush().unwrap();
+        assert_eq!(first.spans[0].id, second.spans[0].id);
+        assert_ne!(second.spans[0].id, third.spans[0].id);
+    }
+
+    #[test]
+    fn parses_quoted_values() {
+        let mut reader = FmtReader::new();
+        let line = r#"DEBUG app: request done path="/a \"b\"" note="x\ny""#;
+        reader.read_line(line).unwrap();
+        let event = reader.flush().unwrap();
+        assert_eq!(message(&event), "request done");
+        assert_eq!(event.fields["path"], FieldValue::Str("/a \"b\"".to_owned()));
+        assert_eq!(event.fields["note"], FieldValue::Str("x\ny".to_owned()));
+    }
+
+    #[test]
+    fn appends_continuation_lines() {
+        let mut reader = FmtReader::new();
+        reader.read_line(" WARN app: panicked").unwrap();
+        assert!(reader.read_line("   at src/main.rs:3").unwrap().is_none());
+        assert!(reader.read_line("info: not a level").unwrap().is_none());
+        let event = reader.read_line(" INFO app: next").unwrap().unwrap();
+        assert_eq!(message(&event), "panicked\n   at src/main.rs:3\ninfo: not a level");
+        assert_eq!(message(&reader.flush().unwrap()), "next");
+    }
+
+    #[test]
+    fn rejects_lines_before_the_first_event() {
+        let mut reader = FmtReader::new();
+        assert!(reader.read_line("garbage").is_err());
+        assert!(reader.read_line("2024-05-01T12:00:00Z garbage").is_err());
+        assert!(reader.read_line("").unwrap().is_none());
+        assert!(reader.flush().is_none());
+    }
+}
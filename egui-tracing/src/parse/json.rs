@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+use serde::de::Error as _;
+use serde_json::{Map, Value};
+use tracing::Level;
+
+use super::text::new_event;
+use super::{LogReader, ParseLineError, SpanIds};
+use crate::tracing::{CollectedEvent, EventCollector, FieldValue, SpanInfo};
+
+/// Keys written by `tracing_subscriber`, or by [`export`](crate::tracing::export)
+/// for `source`, next to the event's fields. Other top-level keys are fields,
+/// as written with `flatten_event(true)`.
+const KNOWN_KEYS: [&str; 11] = [
+    "timestamp",
+    "level",
+    "target",
+    "fields",
+    "filename",
+    "line_number",
+    "span",
+    "spans",
+    "threadName",
+    "threadId",
+    "source",
+];
+
+/// Parses the JSON lines written by `tracing_subscriber::fmt().json()`, one
+/// event per line.
+///
+/// The JSON format does not identify spans, so spans with the same name and
+/// fields below the same parents are taken to be the same span and share a
+/// [`SpanInfo::id`]. Their target and level are not logged either and are
+/// taken from the first event inside them.
+#[derive(Debug, Default)]
+pub struct JsonReader {
+    spans: SpanIds,
+    last_time: Option<DateTime<Local>>,
+}
+
+impl JsonReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates span ids like `collector`, so that they don't collide with
+    /// the ids of its live spans or of spans read from other files.
+    pub fn for_collector(collector: &EventCollector) -> Self {
+        Self {
+            spans: SpanIds::for_collector(collector),
+            last_time: None,
+        }
+    }
+
+    /// Parses a single line. Events without a timestamp, e.g. logged with
+    /// `without_time()`, get the time of the previous event.
+    pub fn parse_line(
+        &mut self,
+        line: &str,
+    ) -> Result<CollectedEvent, serde_json::Error> {
+        let mut object: Map<String, Value> = serde_json::from_str(line)?;
+
+        let level = match object.get("level").and_then(Value::as_str) {
+            Some(level) => Level::from_str(level)
+                .map_err(|_| invalid(format!("invalid level `{}`", level)))?,
+            None => return Err(invalid("missing level")),
+        };
+        let time = match object.get("timestamp").and_then(Value::as_str) {
+            Some(timestamp) => DateTime::parse_from_rfc3339(timestamp)
+                .map_err(|err| invalid(format!("invalid timestamp: {}", err)))?
+                .with_timezone(&Local),
+            None => self.last_time.unwrap_or_else(Local::now),
+        };
+        self.last_time = Some(time);
+
+        let target = take_string(&mut object, "target").unwrap_or_default();
+        let file = take_string(&mut object, "filename");
+        let line = object
+            .get("line_number")
+            .and_then(Value::as_u64)
+            .and_then(|line| u32::try_from(line).ok());
+        let thread_name = take_string(&mut object, "threadName");
+        let thread_id = take_string(&mut object, "threadId");
+        let source = take_string(&mut object, "source");
+
+        // without the span list, only the innermost span is logged.
+        let span_values = match (object.get("spans"), object.get("span")) {
+            (Some(Value::Array(values)), _) => values.as_slice(),
+            (_, Some(span)) => std::slice::from_ref(span),
+            _ => &[],
+        };
+        let mut spans = Vec::new();
+        let mut path = String::new();
+        for value in span_values {
+            let Value::Object(span) = value else { continue };
+            path.push_str(&value.to_string());
+            let span = self
+                .spans
+                .get(&path, |id| span_info(id, span, &target, level));
+            spans.push(span);
+        }
+
+        let mut fields = match object.remove("fields") {
+            Some(Value::Object(fields)) => convert_fields(fields),
+            _ => BTreeMap::new(),
+        };
+        let flattened = object
+            .into_iter()
+            .filter(|(key, _)| !KNOWN_KEYS.contains(&key.as_str()));
+        fields.extend(convert_fields(flattened));
+
+        let mut event = new_event(level, target, time);
+        if let (Some(file), Some(line)) = (&file, line) {
+            event.name = format!("event {}:{}", file, line);
+        }
+        event.fields = fields;
+        event.spans = spans;
+        event.file = file;
+        event.line = line;
+        event.thread_id = thread_id;
+        event.thread_name = thread_name;
+        event.source = source;
+        Ok(event)
+    }
+}
+
+impl LogReader for JsonReader {
+    fn read_line(&mut self, line: &str) -> Result<Option<CollectedEvent>, ParseLineError> {
+        if line.trim().is_empty() {
+            return Ok(None);
+        }
+        self.parse_line(line).map(Some).map_err(|err| ParseLineError {
+            reason: err.to_string(),
+        })
+    }
+}
+
+fn invalid(message: impl Display) -> serde_json::Error {
+    serde_json::Error::custom(message)
+}
+
+fn take_string(object: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match object.remove(key)? {
+        Value::String(value) => Some(value),
+        value => Some(value.to_string()),
+    }
+}
+
+fn span_info(
+    id: u64,
+    span: &Map<String, Value>,
+    target: &str,
+    level: Level,
+) -> SpanInfo {
+    let name = span.get("name").and_then(Value::as_str).unwrap_or_default();
+    let fields = span
+        .iter()
+        .filter(|(key, _)| *key != "name")
+        .map(|(key, value)| (key.clone(), value.clone()));
+    SpanInfo {
+        id,
+        name: name.to_owned(),
+        target: target.to_owned(),
+        level,
+        fields: convert_fields(fields),
+    }
+}
+
+fn convert_fields(
+    fields: impl IntoIterator<Item = (String, Value)>,
+) -> BTreeMap<String, FieldValue> {
+    fields
+        .into_iter()
+        .map(|(key, value)| (key, field_value(value)))
+        .collect()
+}
+
+fn field_value(value: Value) -> FieldValue {
+    match value {
+        Value::Bool(value) => FieldValue::Bool(value),
+        Value::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                FieldValue::I64(value)
+            } else if let Some(value) = number.as_u64() {
+                FieldValue::U64(value)
+            } else {
+                FieldValue::F64(number.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(value) => FieldValue::Str(value),
+        value => FieldValue::Debug(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    use super::*;
+    use crate::tracing::export::{export, ExportFormat};
+
+    #[test]
+    fn parses_spans() {
+        let mut reader = JsonReader::new();
+        let line = concat!(
+            r#"{"timestamp":"2024-05-01T12:00:00.250000Z","level":"INFO","#,
+            r#""fields":{"message":"sent","bytes":512},"target":"my_app::net","#,
+            r#""filename":"src/net.rs","line_number":42,"#,
+            r#""span":{"peer":"10.0.0.1","name":"conn"},"#,
+            r#""spans":[{"id":3,"method":"GET","name":"request"},"#,
+            r#"{"peer":"10.0.0.1","name":"conn"}],"#,
+            r#""threadName":"main","threadId":"ThreadId(1)"}"#,
+        );
+        let event = reader.parse_line(line).unwrap();
+
+        let time = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        assert_eq!(event.time, time + chrono::Duration::milliseconds(250));
+        assert_eq!(event.level, Level::INFO);
+        assert_eq!(event.target, "my_app::net");
+        assert_eq!(event.fields["message"], FieldValue::Str("sent".to_owned()));
+        assert_eq!(event.fields["bytes"], FieldValue::I64(512));
+        assert_eq!(event.name, "event src/net.rs:42");
+        assert_eq!(event.thread_name.as_deref(), Some("main"));
+        assert_eq!(event.thread_id.as_deref(), Some("ThreadId(1)"));
+        let spans: Vec<_> = event.spans.iter().map(|span| span.name.as_str()).collect();
+        assert_eq!(spans, ["request", "conn"]);
+        assert_eq!(event.spans[0].fields["id"], FieldValue::I64(3));
+        assert_eq!(event.spans[0].fields["method"], FieldValue::Str("GET".to_owned()));
+        assert!(!event.spans[1].fields.contains_key("name"));
+
+        // the same spans in the next event are the same spans.
+        let next = reader.parse_line(line).unwrap();
+        assert_eq!(next.spans[1].id, event.spans[1].id);
+        assert_ne!(next.spans[0].id, next.spans[1].id);
+    }
+
+    #[test]
+    fn parses_innermost_span() {
+        let mut reader = JsonReader::new();
+        let line = concat!(
+            r#"{"timestamp":"2024-05-01T12:00:00Z","level":"DEBUG","#,
+            r#""fields":{"message":"hi"},"target":"app","span":{"id":3,"name":"conn"}}"#,
+        );
+        let event = reader.parse_line(line).unwrap();
+        assert_eq!(event.spans.len(), 1);
+        assert_eq!(event.spans[0].name, "conn");
+        assert_eq!(event.spans[0].fields["id"], FieldValue::I64(3));
+        assert_eq!(event.spans[0].target, "app");
+    }
+
+    #[test]
+    fn parses_flattened_fields() {
+        let mut reader = JsonReader::new();
+        let line = concat!(
+            r#"{"timestamp":"2024-05-01T12:00:00Z","level":"WARN","#,
+            r#""message":"slow","elapsed_ms":250,"cached":false,"target":"app"}"#,
+        );
+        let event = reader.parse_line(line).unwrap();
+        assert_eq!(event.fields.len(), 3);
+        assert_eq!(event.fields["message"], FieldValue::Str("slow".to_owned()));
+        assert_eq!(event.fields["elapsed_ms"], FieldValue::I64(250));
+        assert_eq!(event.fields["cached"], FieldValue::Bool(false));
+        assert_eq!(event.target, "app");
+    }
+
+    #[test]
+    fn inherits_missing_timestamp() {
+        let mut reader = JsonReader::new();
+        let first = reader
+            .parse_line(r#"{"timestamp":"2024-05-01T12:00:00Z","level":"INFO","target":"app"}"#)
+            .unwrap();
+        let second = reader
+            .parse_line(r#"{"level":"INFO","fields":{"message":"later"},"target":"app"}"#)
+            .unwrap();
+        assert_eq!(first.time, Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        assert_eq!(second.time, first.time);
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        let mut reader = JsonReader::new();
+        let err = reader
+            .parse_line(r#"{"level":"VERBOSE","target":"app"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid level `VERBOSE`"), "{}", err);
+        assert!(reader.parse_line(r#"{"target":"app"}"#).is_err());
+        assert!(reader.parse_line("INFO app: not json").is_err());
+        assert!(reader.read_line("").unwrap().is_none());
+    }
+
+    #[test]
+    fn round_trips_exported_events() {
+        let collector = EventCollector::default();
+        let subscriber = tracing_subscriber::registry().with(collector.clone()).set_default();
+        ::tracing::info_span!("request", id = 3, path = "/a").in_scope(|| {
+            ::tracing::warn!(elapsed_ms = 250u64, ratio = 0.5, ok = false, "slow \"request\"");
+        });
+        ::tracing::error!("failed");
+        drop(subscriber);
+        let mut events = collector.events();
+        events[1].source = Some("server[42]".to_owned());
+
+        let mut jsonl = Vec::new();
+        export(&events, ExportFormat::JsonLines, &mut jsonl).unwrap();
+        let imported = EventCollector::default().with_capacity(10);
+        let summary = imported.import_json(jsonl.as_slice()).unwrap();
+        assert_eq!(summary.imported, 2);
+        assert!(summary.invalid_lines.is_empty());
+
+        // JSON keeps the text of the values, not whether e.g. a number was
+        // signed.
+        let text = |fields: &BTreeMap<String, FieldValue>| -> Vec<(String, String)> {
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.to_string()))
+                .collect()
+        };
+        for (before, after) in events.iter().zip(imported.events()) {
+            // timestamps are exported with microseconds.
+            assert!((before.time - after.time).num_microseconds().unwrap().abs() < 1);
+            assert_eq!(before.level, after.level);
+            assert_eq!(before.target, after.target);
+            assert_eq!(text(&before.fields), text(&after.fields));
+            assert_eq!(before.file, after.file);
+            assert_eq!(before.line, after.line);
+            assert_eq!(before.thread_id, after.thread_id);
+            assert_eq!(before.thread_name, after.thread_name);
+            assert_eq!(before.source, after.source);
+            assert_eq!(before.spans.len(), after.spans.len());
+            for (before, after) in before.spans.iter().zip(&after.spans) {
+                assert_eq!(before.name, after.name);
+                assert_eq!(text(&before.fields), text(&after.fields));
+            }
+        }
+    }
+
+    #[test]
+    fn reports_dropped_events() {
+        let lines = (0..5)
+            .map(|n| format!(r#"{{"level":"INFO","fields":{{"n":{}}},"target":"app"}}"#, n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let collector = EventCollector::default().with_capacity(2);
+        let summary = collector.import_json(lines.as_bytes()).unwrap();
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.dropped, 3);
+        assert_eq!(collector.events()[0].fields["n"], FieldValue::I64(3));
+    }
+}
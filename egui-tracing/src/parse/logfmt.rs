@@ -0,0 +1,125 @@
+use super::text::{new_event, parse_level, parse_pairs, parse_timestamp, strip_ansi, MultiLine};
+use super::{LogReader, ParseLineError};
+use crate::tracing::{CollectedEvent, FieldValue};
+
+/// Parses logfmt lines like
+///
+/// ```text
+/// time=2024-05-01T12:00:00Z level=info target=my_app msg="request done" status=200
+/// ```
+///
+/// The time may also be given as `ts` or `timestamp`, the level as `lvl` or
+/// `severity`, the target as `logger` or `module` and the message as
+/// `message`. Other pairs become fields. Lines without a level continue the
+/// previous message.
+#[derive(Debug, Default)]
+pub struct LogfmtReader {
+    lines: MultiLine,
+}
+
+impl LogfmtReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn parse(&mut self, line: &str) -> Option<CollectedEvent> {
+        let pairs = parse_pairs(line)?;
+        let mut level = None;
+        let mut time = None;
+        let mut target = String::new();
+        let mut fields = Vec::new();
+        for (key, value) in pairs {
+            match key {
+                "level" | "lvl" | "severity" => level = parse_level(&value.into_string()),
+                "time" | "ts" | "timestamp" => time = parse_timestamp(&value.into_string()),
+                "target" | "logger" | "module" => target = value.into_string(),
+                "msg" | "message" => {
+                    fields.push(("message", FieldValue::Str(value.into_string())));
+                }
+                key => fields.push((key, value.into_field(FieldValue::Str))),
+            }
+        }
+
+        let mut event = new_event(level?, target, self.lines.time(time));
+        for (key, value) in fields {
+            event.fields.insert(key.to_owned(), value);
+        }
+        Some(event)
+    }
+}
+
+impl LogReader for LogfmtReader {
+    fn read_line(&mut self, line: &str) -> Result<Option<CollectedEvent>, ParseLineError> {
+        let line = strip_ansi(line);
+        let event = self.parse(&line);
+        self.lines.read(event, &line)
+    }
+
+    fn flush(&mut self) -> Option<CollectedEvent> {
+        self.lines.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use tracing::Level;
+
+    use super::*;
+
+    #[test]
+    fn parses_line() {
+        let mut reader = LogfmtReader::new();
+        let line = "time=2024-05-01T12:00:00Z level=info target=my_app \
+                    msg=\"request done\" status=200 user=bob";
+        assert!(reader.read_line(line).unwrap().is_none());
+        let event = reader.flush().unwrap();
+
+        assert_eq!(event.time, Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        assert_eq!(event.level, Level::INFO);
+        assert_eq!(event.target, "my_app");
+        assert_eq!(event.fields["message"], FieldValue::Str("request done".to_owned()));
+        assert_eq!(event.fields["status"], FieldValue::I64(200));
+        assert_eq!(event.fields["user"], FieldValue::Str("bob".to_owned()));
+    }
+
+    #[test]
+    fn accepts_alternative_keys() {
+        let mut reader = LogfmtReader::new();
+        reader.read_line("ts=2024-05-01T12:00:00Z lvl=WARNING logger=x message=hi").unwrap();
+        let event = reader.flush().unwrap();
+        assert_eq!(event.level, Level::WARN);
+        assert_eq!(event.target, "x");
+        assert_eq!(event.fields["message"], FieldValue::Str("hi".to_owned()));
+    }
+
+    #[test]
+    fn parses_quoted_values() {
+        let mut reader = LogfmtReader::new();
+        reader.read_line(r#"level=error msg="say \"hi\"\n" path="C:\\tmp" n="1""#).unwrap();
+        let event = reader.flush().unwrap();
+        assert_eq!(event.fields["message"], FieldValue::Str("say \"hi\"\n".to_owned()));
+        assert_eq!(event.fields["path"], FieldValue::Str("C:\\tmp".to_owned()));
+        assert_eq!(event.fields["n"], FieldValue::Str("1".to_owned()));
+    }
+
+    #[test]
+    fn appends_continuation_lines() {
+        let mut reader = LogfmtReader::new();
+        reader.read_line("level=info msg=first").unwrap();
+        assert!(reader.read_line("  more text").unwrap().is_none());
+        assert!(reader.read_line("key=value without level").unwrap().is_none());
+        let event = reader.read_line("level=debug msg=second").unwrap().unwrap();
+        let message = FieldValue::Str("first\n  more text\nkey=value without level".to_owned());
+        assert_eq!(event.fields["message"], message);
+    }
+
+    #[test]
+    fn rejects_lines_before_the_first_event() {
+        let mut reader = LogfmtReader::new();
+        assert!(reader.read_line("no pairs here").is_err());
+        assert!(reader.read_line("level=loud msg=x").is_err());
+        assert!(reader.read_line(r#"level=info msg="unterminated"#).is_err());
+        assert!(reader.flush().is_none());
+    }
+}
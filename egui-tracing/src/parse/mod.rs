@@ -0,0 +1,137 @@
+//! Readers turning log files back into [`CollectedEvent`]s, so that they can
+//! be shown like live events, see [`EventCollector::import`].
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::tracing::{CollectedEvent, EventCollector, SpanInfo};
+
+pub mod env_logger;
+pub mod fmt;
+pub mod json;
+pub mod logfmt;
+mod text;
+
+pub use env_logger::EnvLoggerReader;
+pub use fmt::FmtReader;
+pub use json::JsonReader;
+pub use logfmt::LogfmtReader;
+
+/// Names of the formats supported by [`reader_for_format`].
+pub const FORMATS: [&str; 4] = ["json", "fmt", "logfmt", "env_logger"];
+
+/// Turns the lines of a log file into events.
+pub trait LogReader: Send {
+    /// Reads the next line, without its line ending. Returns an event once
+    /// it is complete, which for formats with multi-line messages is only
+    /// known when the next event starts.
+    fn read_line(&mut self, line: &str) -> Result<Option<CollectedEvent>, ParseLineError>;
+
+    /// Returns the event still being read, at the end of the input or when
+    /// no more lines arrived for now.
+    fn flush(&mut self) -> Option<CollectedEvent> {
+        None
+    }
+}
+
+/// Creates a reader for one of the [`FORMATS`], adding its events to
+/// `collector`.
+pub fn reader_for_format(
+    format: &str,
+    collector: &EventCollector,
+) -> Option<Box<dyn LogReader>> {
+    match format {
+        "json" => Some(Box::new(JsonReader::for_collector(collector))),
+        "fmt" => Some(Box::new(FmtReader::for_collector(collector))),
+        "logfmt" => Some(Box::new(LogfmtReader::new())),
+        "env_logger" => Some(Box::new(EnvLoggerReader::new())),
+        _ => None,
+    }
+}
+
+/// A line a [`LogReader`] could not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLineError {
+    pub reason: String,
+}
+
+impl Display for ParseLineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl Error for ParseLineError {}
+
+/// Outcome of importing a log file into a collector.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of events read that the collector still retains at the end of
+    /// the import.
+    pub imported: usize,
+    /// Number of events read but dropped or evicted again because of the
+    /// collector's capacity, see [`EvictionPolicy`](crate::tracing::EvictionPolicy).
+    pub dropped: usize,
+    /// Numbers of the lines that could not be parsed, starting at 1.
+    pub invalid_lines: Vec<usize>,
+}
+
+/// Number of spans a [`LogReader`] remembers, see [`SpanIds`].
+const MAX_SPANS: usize = 4096;
+
+/// Ids of the spans read from a log. Logs don't identify spans, so spans with
+/// the same name and fields below the same parents are taken to be the same
+/// span. Only the latest [`MAX_SPANS`] spans are remembered; a span seen again
+/// after being forgotten gets a new id.
+#[derive(Debug)]
+struct SpanIds {
+    spans: HashMap<String, Arc<SpanInfo>>,
+    /// Keys of `spans`, oldest first.
+    order: VecDeque<String>,
+    next_span_id: Arc<AtomicU64>,
+}
+
+impl Default for SpanIds {
+    fn default() -> Self {
+        Self {
+            spans: HashMap::new(),
+            order: VecDeque::new(),
+            next_span_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+}
+
+impl SpanIds {
+    fn for_collector(collector: &EventCollector) -> Self {
+        Self {
+            spans: HashMap::new(),
+            order: VecDeque::new(),
+            next_span_id: collector.span_ids(),
+        }
+    }
+
+    /// Returns the span identified by `path`, the text of the span and its
+    /// parents, created with `new_span` the first time.
+    fn get(
+        &mut self,
+        path: &str,
+        new_span: impl FnOnce(u64) -> SpanInfo,
+    ) -> Arc<SpanInfo> {
+        if let Some(span) = self.spans.get(path) {
+            return span.clone();
+        }
+        let id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        let span = Arc::new(new_span(id));
+        if self.order.len() >= MAX_SPANS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.spans.remove(&oldest);
+            }
+        }
+        self.spans.insert(path.to_owned(), span.clone());
+        self.order.push_back(path.to_owned());
+        span
+    }
+}
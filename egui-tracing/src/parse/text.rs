@@ -0,0 +1,374 @@
+//! Helpers shared by the readers of plain-text formats.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use tracing::Level;
+
+use super::ParseLineError;
+use crate::tracing::{CollectedEvent, FieldValue};
+
+/// An event with the given level, target and time, and nothing else known.
+pub(super) fn new_event(
+    level: Level,
+    target: String,
+    time: DateTime<Local>,
+) -> CollectedEvent {
+    CollectedEvent {
+        target,
+        level,
+        fields: BTreeMap::new(),
+        time,
+        spans: Vec::new(),
+        name: "event".to_owned(),
+        module_path: None,
+        file: None,
+        line: None,
+        thread_id: None,
+        thread_name: None,
+        source: None,
+    }
+}
+
+/// Removes the escape sequences of colored terminal output.
+pub(super) fn strip_ansi(line: &str) -> Cow<'_, str> {
+    if !line.contains('\x1b') {
+        return Cow::Borrowed(line);
+    }
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI, e.g. colors, ends with a byte in `@..=~`
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC, e.g. hyperlinks, ends with BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// Parses a level in any case, including the names used by other logging
+/// libraries, e.g. `warning` or `fatal`.
+pub(super) fn parse_level(text: &str) -> Option<Level> {
+    match text.to_ascii_lowercase().as_str() {
+        "warning" => Some(Level::WARN),
+        "err" | "fatal" | "critical" | "crit" => Some(Level::ERROR),
+        "trace" | "debug" | "info" | "warn" | "error" => Level::from_str(text).ok(),
+        _ => None,
+    }
+}
+
+/// Parses an RFC 3339 timestamp, or a date and time without offset in local
+/// time.
+pub(super) fn parse_timestamp(text: &str) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Local));
+    }
+    let time = NaiveDateTime::from_str(text).ok()?;
+    Local.from_local_datetime(&time).earliest()
+}
+
+/// A value of a `key=value` pair.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum PairValue<'a> {
+    Quoted(String),
+    Bare(&'a str),
+}
+
+impl PairValue<'_> {
+    /// Converts the value, keeping numbers and booleans typed. Bare text is
+    /// converted with `bare`, as its meaning differs between formats.
+    pub(super) fn into_field(self, bare: fn(String) -> FieldValue) -> FieldValue {
+        let text = match self {
+            PairValue::Quoted(text) => return FieldValue::Str(text),
+            PairValue::Bare(text) => text,
+        };
+        if let Ok(value) = text.parse() {
+            FieldValue::I64(value)
+        } else if let Ok(value) = text.parse() {
+            FieldValue::U64(value)
+        } else if let (true, Ok(value)) = (looks_numeric(text), text.parse()) {
+            FieldValue::F64(value)
+        } else if let Ok(value) = text.parse() {
+            FieldValue::Bool(value)
+        } else {
+            bare(text.to_owned())
+        }
+    }
+
+    pub(super) fn into_string(self) -> String {
+        match self {
+            PairValue::Quoted(text) => text,
+            PairValue::Bare(text) => text.to_owned(),
+        }
+    }
+}
+
+/// Excludes the words `f64` parses, like `inf` or `NaN`.
+fn looks_numeric(text: &str) -> bool {
+    text.trim_start_matches('-')
+        .starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// Parses space-separated `key=value` pairs, where values may be quoted.
+/// `None` if `text` isn't made of such pairs only.
+pub(super) fn parse_pairs(text: &str) -> Option<Vec<(&str, PairValue<'_>)>> {
+    let mut pairs = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let (key, value, after) = parse_pair(rest)?;
+        pairs.push((key, value));
+        rest = after.trim_start();
+    }
+    Some(pairs)
+}
+
+/// Parses a `key=value` pair at the start of `text`, followed by a space or
+/// the end of `text`. Returns the key, the value and the rest of `text`.
+fn parse_pair(text: &str) -> Option<(&str, PairValue<'_>, &str)> {
+    let token_end = text.find(' ').unwrap_or(text.len());
+    let eq = text[..token_end].find('=')?;
+    let key = &text[..eq];
+    if key.is_empty() || !key.chars().all(is_key_char) {
+        return None;
+    }
+
+    let rest = &text[eq + 1..];
+    let (value, after) = if rest.starts_with('"') {
+        let (value, len) = parse_quoted(rest)?;
+        (PairValue::Quoted(value), &rest[len..])
+    } else {
+        let end = rest.find(' ').unwrap_or(rest.len());
+        (PairValue::Bare(&rest[..end]), &rest[end..])
+    };
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    Some((key, value, after))
+}
+
+/// Parses a double-quoted string with backslash escapes at the start of
+/// `text`. Returns it with the length of its source.
+fn parse_quoted(text: &str) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, index + 1)),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                c @ ('"' | '\\') => value.push(c),
+                c => {
+                    value.push('\\');
+                    value.push(c);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Splits a message followed by `key=value` pairs, as written by
+/// `tracing_subscriber::fmt`. The pairs are the run of pairs reaching the end
+/// of `text`, found in a single pass over the words.
+pub(super) fn split_pairs(text: &str) -> (&str, Vec<(&str, PairValue<'_>)>) {
+    let mut pairs = Vec::new();
+    let mut start = None;
+    let mut position = 0;
+    while position < text.len() {
+        match parse_pair(&text[position..]) {
+            Some((key, value, after)) => {
+                start.get_or_insert(position);
+                pairs.push((key, value));
+                position = text.len() - after.trim_start().len();
+            }
+            None => {
+                // the message continues, and the pairs start after it.
+                start = None;
+                pairs.clear();
+                position = match text[position..].find(' ') {
+                    Some(index) => position + index + 1,
+                    None => text.len(),
+                };
+            }
+        }
+    }
+    match start {
+        Some(start) => (text[..start].trim_end(), pairs),
+        None => (text, pairs),
+    }
+}
+
+/// Lets lines that don't start an event continue the message of the
+/// previous one, e.g. for messages with line breaks or printed backtraces.
+#[derive(Debug, Default)]
+pub(super) struct MultiLine {
+    pending: Option<CollectedEvent>,
+    last_time: Option<DateTime<Local>>,
+}
+
+impl MultiLine {
+    /// The time of an event, which without a timestamp in the log is the
+    /// time of the previous event.
+    pub(super) fn time(&mut self, time: Option<DateTime<Local>>) -> DateTime<Local> {
+        let time = time.or(self.last_time).unwrap_or_else(Local::now);
+        self.last_time = Some(time);
+        time
+    }
+
+    /// Takes the `event` parsed from `line`, or appends `line` to the
+    /// pending event if it doesn't start one. Returns the previous event
+    /// once the next one starts.
+    pub(super) fn read(
+        &mut self,
+        event: Option<CollectedEvent>,
+        line: &str,
+    ) -> Result<Option<CollectedEvent>, ParseLineError> {
+        if let Some(event) = event {
+            return Ok(self.pending.replace(event));
+        }
+        if line.trim().is_empty() {
+            return Ok(None);
+        }
+        let Some(pending) = &mut self.pending else {
+            return Err(ParseLineError {
+                reason: "expected the start of an event".to_owned(),
+            });
+        };
+        match pending.fields.get_mut("message") {
+            Some(FieldValue::Str(message)) => {
+                message.push('\n');
+                message.push_str(line);
+            }
+            _ => {
+                let message = FieldValue::Str(line.to_owned());
+                pending.fields.insert("message".to_owned(), message);
+            }
+        }
+        Ok(None)
+    }
+
+    pub(super) fn flush(&mut self) -> Option<CollectedEvent> {
+        self.pending.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pairs() {
+        let pairs = parse_pairs(r#"a=1  b="x \"y\"\tz" c.d=-2.5"#).unwrap();
+        assert_eq!(
+            pairs,
+            [
+                ("a", PairValue::Bare("1")),
+                ("b", PairValue::Quoted("x \"y\"\tz".to_owned())),
+                ("c.d", PairValue::Bare("-2.5")),
+            ]
+        );
+        assert_eq!(parse_pairs(""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_malformed_pairs() {
+        assert_eq!(parse_pairs("a=1 b"), None);
+        assert_eq!(parse_pairs(r#"a="unterminated"#), None);
+        assert_eq!(parse_pairs(r#"a="x"y"#), None);
+        assert_eq!(parse_pairs("=1"), None);
+        assert_eq!(parse_pairs("a b=1"), None);
+    }
+
+    #[test]
+    fn splits_message_from_pairs() {
+        let (message, pairs) = split_pairs(r#"sent a=b to c bytes=512 path="/a b""#);
+        assert_eq!(message, "sent a=b to c");
+        assert_eq!(
+            pairs,
+            [
+                ("bytes", PairValue::Bare("512")),
+                ("path", PairValue::Quoted("/a b".to_owned())),
+            ]
+        );
+        assert_eq!(split_pairs("no pairs here"), ("no pairs here", Vec::new()));
+        assert_eq!(split_pairs("x=1").0, "");
+        assert_eq!(split_pairs(r#"a=1 "b"#), (r#"a=1 "b"#, Vec::new()));
+    }
+
+    #[test]
+    fn converts_values() {
+        let field = |text| PairValue::Bare(text).into_field(FieldValue::Debug);
+        assert_eq!(field("-3"), FieldValue::I64(-3));
+        assert_eq!(field("18446744073709551615"), FieldValue::U64(u64::MAX));
+        assert_eq!(field("1.5"), FieldValue::F64(1.5));
+        assert_eq!(field("true"), FieldValue::Bool(true));
+        assert_eq!(field("NaN"), FieldValue::Debug("NaN".to_owned()));
+        let quoted = PairValue::Quoted("1".to_owned()).into_field(FieldValue::Debug);
+        assert_eq!(quoted, FieldValue::Str("1".to_owned()));
+    }
+
+    #[test]
+    fn strips_ansi() {
+        let line = "\x1b[2m12:00\x1b[0m \x1b]8;;http://x\x1b\\link\x1b]8;;\x07 \x1b[33mWARN\x1b[0m";
+        assert_eq!(strip_ansi(line), "12:00 link WARN");
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(parse_level("INFO"), Some(Level::INFO));
+        assert_eq!(parse_level("Warning"), Some(Level::WARN));
+        assert_eq!(parse_level("fatal"), Some(Level::ERROR));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn continues_pending_event() {
+        let mut lines = MultiLine::default();
+        let time = lines.time(None);
+        assert!(lines.read(None, "orphan").is_err());
+
+        let mut event = new_event(Level::INFO, "t".to_owned(), time);
+        let message = FieldValue::Str("first".to_owned());
+        event.fields.insert("message".to_owned(), message);
+        assert!(lines.read(Some(event), "").unwrap().is_none());
+        assert!(lines.read(None, "  second").unwrap().is_none());
+        assert!(lines.read(None, " ").unwrap().is_none());
+
+        let event = lines.flush().unwrap();
+        assert_eq!(event.fields["message"], FieldValue::Str("first\n  second".to_owned()));
+        assert!(lines.flush().is_none());
+    }
+}
@@ -0,0 +1,565 @@
+//! Forwarding events to another process over TCP, e.g. from an application
+//! on a headless machine to a [`Logs`](crate::Logs) widget elsewhere.
+//!
+//! An [`EventForwarder`] sends the events of an [`EventCollector`] to an
+//! [`EventReceiver`], which adds them to its own collector with the label of
+//! the forwarding process as [`CollectedEvent::source`].
+//!
+//! Each message is a big-endian `u32` length followed by that many bytes of
+//! JSON. The first message of a connection is `{"source":"<label>"}`, the
+//! others are events in the JSON Lines format of
+//! [`export`](crate::tracing::export).
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::parse::JsonReader;
+use crate::tracing::export::event_json;
+use crate::tracing::{CollectedEvent, EventCollector, EventCursor};
+
+/// Messages larger than this close the connection.
+const MAX_MESSAGE_LEN: u32 = 16 * 1024 * 1024;
+
+/// How often the forwarder checks the collector for new events.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the receiver checks for new connections.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends the events of an [`EventCollector`] to an [`EventReceiver`],
+/// reconnecting whenever the connection is lost.
+///
+/// The collector keeps the events while disconnected, so give it a capacity
+/// to bound the memory used when the receiver is away for long. Events sent
+/// right before a connection is lost may not arrive.
+#[derive(Debug, Clone)]
+pub struct EventForwarder {
+    addr: String,
+    source: Option<String>,
+    retry_interval: Duration,
+}
+
+impl EventForwarder {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            source: None,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Label of this process at the receiver, by default the name of the
+    /// executable and the process id, e.g. `server[4242]`.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Time between connection attempts, one second by default.
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Starts forwarding on a background thread, beginning with the events
+    /// the collector already retains. Forwarding continues when the handle
+    /// is dropped.
+    pub fn spawn(self, collector: &EventCollector) -> ForwarderHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let connected = Arc::new(AtomicBool::new(false));
+        let mut forwarding = Forwarding {
+            source: self.source.clone().unwrap_or_else(default_source),
+            forwarder: self,
+            collector: collector.clone(),
+            cursor: EventCursor::default(),
+            next_seq: 0,
+            pending: VecDeque::new(),
+            stop: stop.clone(),
+            connected: connected.clone(),
+        };
+        let thread = thread::Builder::new()
+            .name("egui_tracing forwarder".to_owned())
+            .spawn(move || forwarding.run())
+            .unwrap();
+        ForwarderHandle {
+            stop,
+            connected,
+            thread,
+        }
+    }
+}
+
+/// Controls a running [`EventForwarder`].
+#[derive(Debug)]
+pub struct ForwarderHandle {
+    stop: Arc<AtomicBool>,
+    connected: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl ForwarderHandle {
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Sends the events collected so far, if connected, and stops
+    /// forwarding.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.thread().unpark();
+        let _ = self.thread.join();
+    }
+}
+
+struct Forwarding {
+    forwarder: EventForwarder,
+    source: String,
+    collector: EventCollector,
+    cursor: EventCursor,
+    /// Sequence number following the last event taken from the collector,
+    /// so that events are taken once even when the cursor is reset.
+    next_seq: u64,
+    /// Events taken from the collector but not sent yet.
+    pending: VecDeque<Arc<CollectedEvent>>,
+    stop: Arc<AtomicBool>,
+    connected: Arc<AtomicBool>,
+}
+
+impl Forwarding {
+    fn run(&mut self) {
+        while !self.stop.load(Ordering::Relaxed) {
+            if let Ok(stream) = TcpStream::connect(&self.forwarder.addr) {
+                let _ = stream.set_nodelay(true);
+                self.connected.store(true, Ordering::Relaxed);
+                let result = self.send(&stream);
+                self.connected.store(false, Ordering::Relaxed);
+                if result.is_ok() {
+                    return;
+                }
+            }
+            thread::park_timeout(self.forwarder.retry_interval);
+        }
+    }
+
+    /// Sends events until stopped, or until the connection fails.
+    fn send(&mut self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_millis(1)))?;
+        let mut writer = BufWriter::new(stream);
+        let hello = encode_message(&json!({ "source": self.source }))
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "source too long"))?;
+        writer.write_all(&hello)?;
+        loop {
+            let stopping = self.stop.load(Ordering::Relaxed);
+            let since = self.collector.events_since(self.cursor);
+            self.cursor = since.cursor;
+            for (seq, event) in since.seqs.into_iter().zip(since.events) {
+                if seq >= self.next_seq {
+                    self.pending.push_back(event);
+                    self.next_seq = seq + 1;
+                }
+            }
+            while let Some(event) = self.pending.front() {
+                // events too large to send are skipped.
+                if let Some(message) = encode_message(&event_json(event)) {
+                    writer.write_all(&message)?;
+                }
+                self.pending.pop_front();
+            }
+            writer.flush()?;
+            if stopping {
+                return Ok(());
+            }
+            if is_closed(stream) {
+                return Err(ErrorKind::ConnectionAborted.into());
+            }
+            thread::park_timeout(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Whether the receiver closed the connection, which is otherwise only
+/// noticed once writing fails.
+fn is_closed(stream: &TcpStream) -> bool {
+    match stream.peek(&mut [0]) {
+        Ok(read) => read == 0,
+        Err(err) => !matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut),
+    }
+}
+
+fn default_source() -> String {
+    let name = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.file_stem().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown".to_owned());
+    format!("{}[{}]", name, std::process::id())
+}
+
+/// Prefixes the JSON of `message` with its length, `None` if it's too large.
+fn encode_message(message: &Value) -> Option<Vec<u8>> {
+    let json = serde_json::to_vec(message).ok()?;
+    let len = u32::try_from(json.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_LEN)?;
+    let mut bytes = Vec::with_capacity(4 + json.len());
+    bytes.extend_from_slice(&len.to_be_bytes());
+    bytes.extend_from_slice(&json);
+    Some(bytes)
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(ErrorKind::InvalidData, "message too large"));
+    }
+    let mut message = vec![0; len as usize];
+    reader.read_exact(&mut message)?;
+    Ok(message)
+}
+
+/// A process that connected to an [`EventReceiver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSource {
+    /// Label given to the events of the process.
+    pub label: String,
+    /// Address of the current or last connection.
+    pub addr: SocketAddr,
+    pub connected: bool,
+    /// Number of times the process connected.
+    pub connections: u32,
+    /// Number of events received over all connections.
+    pub events: u64,
+}
+
+/// Accepts connections from [`EventForwarder`]s on a background thread and
+/// adds their events to an [`EventCollector`].
+///
+/// A process that reconnects with the same label continues its source. If
+/// another connection is using the label, a number is appended to it.
+#[derive(Debug)]
+pub struct EventReceiver {
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+    thread: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    collector: EventCollector,
+    sources: Mutex<Vec<RemoteSource>>,
+    /// Open connections, to close them when stopping.
+    streams: Mutex<HashMap<u64, TcpStream>>,
+    /// Threads receiving from the connections, to wait for them when
+    /// stopping.
+    threads: Mutex<Vec<JoinHandle<()>>>,
+    next_stream_id: AtomicU64,
+    stop: AtomicBool,
+}
+
+impl EventReceiver {
+    pub fn bind(addr: impl ToSocketAddrs, collector: &EventCollector) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            collector: collector.clone(),
+            sources: Mutex::new(Vec::new()),
+            streams: Mutex::new(HashMap::new()),
+            threads: Mutex::new(Vec::new()),
+            next_stream_id: AtomicU64::new(0),
+            stop: AtomicBool::new(false),
+        });
+        let thread = thread::Builder::new()
+            .name("egui_tracing receiver".to_owned())
+            .spawn({
+                let shared = shared.clone();
+                move || shared.accept(listener)
+            })?;
+        Ok(Self {
+            local_addr,
+            shared,
+            thread,
+        })
+    }
+
+    /// The address the receiver listens on, e.g. to find the port when
+    /// bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The processes that connected so far, in the order they first did.
+    pub fn sources(&self) -> Vec<RemoteSource> {
+        self.shared.sources.lock().unwrap().clone()
+    }
+
+    /// Closes the open connections and stops accepting new ones. No events
+    /// are added to the collector once this returns.
+    pub fn stop(self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        for stream in self.shared.streams.lock().unwrap().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let _ = self.thread.join();
+        let threads = std::mem::take(&mut *self.shared.threads.lock().unwrap());
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+    fn accept(self: Arc<Self>, listener: TcpListener) {
+        while !self.stop.load(Ordering::Relaxed) {
+            let (stream, addr) = match listener.accept() {
+                Ok(connection) => connection,
+                // no connection waiting, or e.g. out of file descriptors.
+                Err(_) => {
+                    thread::sleep(ACCEPT_INTERVAL);
+                    continue;
+                }
+            };
+            let id = self.next_stream_id.fetch_add(1, Ordering::Relaxed);
+            let registered = stream.set_nonblocking(false).and_then(|_| stream.try_clone());
+            let Ok(clone) = registered else { continue };
+            {
+                let mut streams = self.streams.lock().unwrap();
+                // `stop` may have closed the open connections already.
+                if self.stop.load(Ordering::Relaxed) {
+                    let _ = stream.shutdown(Shutdown::Both);
+                    break;
+                }
+                streams.insert(id, clone);
+            }
+
+            let shared = self.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("egui_tracing receiver {}", addr))
+                .spawn(move || {
+                    shared.receive(stream, addr);
+                    shared.streams.lock().unwrap().remove(&id);
+                });
+            match spawned {
+                Ok(thread) => {
+                    let mut threads = self.threads.lock().unwrap();
+                    threads.retain(|thread| !thread.is_finished());
+                    threads.push(thread);
+                }
+                Err(_) => {
+                    self.streams.lock().unwrap().remove(&id);
+                }
+            }
+        }
+    }
+
+    fn receive(&self, stream: TcpStream, addr: SocketAddr) {
+        let mut reader = BufReader::new(stream);
+        let Ok(hello) = read_message(&mut reader) else { return };
+        let label = serde_json::from_slice::<Value>(&hello)
+            .ok()
+            .and_then(|hello| hello.get("source")?.as_str().map(str::to_owned))
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| addr.to_string());
+        let label = self.connect(label, addr);
+
+        let mut json = JsonReader::for_collector(&self.collector);
+        let mut batch = Vec::new();
+        while let Ok(message) = read_message(&mut reader) {
+            let event = std::str::from_utf8(&message)
+                .ok()
+                .and_then(|message| json.parse_line(message).ok());
+            if let Some(mut event) = event {
+                event.source.get_or_insert_with(|| label.clone());
+                batch.push(event);
+            }
+            // add what arrived so far before waiting for more.
+            if reader.buffer().is_empty() {
+                self.add(&label, &mut batch);
+            }
+        }
+        self.add(&label, &mut batch);
+        self.update(&label, |source| source.connected = false);
+    }
+
+    /// Marks the source as connected, returning its label made unique among
+    /// the connected sources.
+    fn connect(&self, label: String, addr: SocketAddr) -> String {
+        let mut sources = self.sources.lock().unwrap();
+        let mut unique = label.clone();
+        let mut number = 1;
+        while sources
+            .iter()
+            .any(|source| source.label == unique && source.connected)
+        {
+            number += 1;
+            unique = format!("{} ({})", label, number);
+        }
+        match sources.iter_mut().find(|source| source.label == unique) {
+            Some(source) => {
+                source.addr = addr;
+                source.connected = true;
+                source.connections += 1;
+            }
+            None => sources.push(RemoteSource {
+                label: unique.clone(),
+                addr,
+                connected: true,
+                connections: 1,
+                events: 0,
+            }),
+        }
+        unique
+    }
+
+    fn add(&self, label: &str, batch: &mut Vec<CollectedEvent>) {
+        if batch.is_empty() {
+            return;
+        }
+        let count = batch.len() as u64;
+        self.collector.extend(batch.drain(..));
+        self.update(label, |source| source.events += count);
+    }
+
+    fn update(&self, label: &str, f: impl FnOnce(&mut RemoteSource)) {
+        let mut sources = self.sources.lock().unwrap();
+        if let Some(source) = sources.iter_mut().find(|source| source.label == label) {
+            f(source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    use super::*;
+    use crate::tracing::EvictionPolicy;
+
+    fn wait_for(receiving: &EventCollector, count: usize) {
+        let start = Instant::now();
+        while receiving.events().len() < count && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn forwards_each_event_once(policy: EvictionPolicy) {
+        let receiving = EventCollector::default();
+        let receiver = EventReceiver::bind("127.0.0.1:0", &receiving).unwrap();
+        let collector = EventCollector::default()
+            .with_capacity(10)
+            .with_eviction_policy(policy);
+        let forwarder = EventForwarder::new(receiver.local_addr().to_string())
+            .with_source("test")
+            .with_retry_interval(Duration::from_millis(10))
+            .spawn(&collector);
+
+        let subscriber = tracing_subscriber::registry().with(collector.clone()).set_default();
+        for n in 0..30u64 {
+            // retained warnings make the oldest info events sit in the middle
+            // once evicted.
+            if n % 5 == 0 {
+                ::tracing::warn!(n);
+            } else {
+                ::tracing::info!(n);
+            }
+            if n % 5 == 4 {
+                wait_for(&receiving, n as usize + 1);
+            }
+            // forwarded events must not be sent again after a clear.
+            if n == 19 {
+                collector.clear();
+            }
+        }
+        // more evictions between two polls than the collector logs, after
+        // which the retained warnings are returned again.
+        for n in 100..130u64 {
+            ::tracing::info!(n);
+        }
+        drop(subscriber);
+        thread::sleep(POLL_INTERVAL * 4);
+        forwarder.stop();
+        receiver.stop();
+
+        let numbers = |events: Vec<CollectedEvent>| -> Vec<u64> {
+            let numbers = events.iter().map(|event| event.fields["n"].as_f64().unwrap());
+            numbers.map(|n| n as u64).collect()
+        };
+        let events = receiving.events();
+        assert!(events.iter().all(|event| event.source.as_deref() == Some("test")));
+        let mut received = numbers(events);
+        received.sort_unstable();
+        let mut unique = received.clone();
+        unique.dedup();
+        assert_eq!(received, unique, "{:?}", policy);
+        // events evicted before being sent are lost, all others arrive.
+        assert_eq!(received[..30], (0..30).collect::<Vec<_>>(), "{:?}", policy);
+        for n in numbers(collector.events()) {
+            assert!(received.contains(&n), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn forwards_each_event_once_when_dropping_oldest() {
+        forwards_each_event_once(EvictionPolicy::DropOldest);
+    }
+
+    #[test]
+    fn forwards_each_event_once_when_dropping_lowest_level() {
+        forwards_each_event_once(EvictionPolicy::DropLowestLevel);
+    }
+
+    #[test]
+    fn reports_sources() {
+        let receiving = EventCollector::default();
+        let receiver = EventReceiver::bind("127.0.0.1:0", &receiving).unwrap();
+        let collector = EventCollector::default();
+        let forwarder = EventForwarder::new(receiver.local_addr().to_string())
+            .with_source("app")
+            .spawn(&collector);
+        let subscriber = tracing_subscriber::registry().with(collector.clone()).set_default();
+        ::tracing::info!("hello");
+        drop(subscriber);
+        wait_for(&receiving, 1);
+        forwarder.stop();
+
+        let sources = receiver.sources();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].label, "app");
+        assert_eq!(sources[0].events, 1);
+        receiver.stop();
+    }
+
+    #[test]
+    fn stop_closes_connections() {
+        let collector = EventCollector::default();
+        let subscriber = tracing_subscriber::registry().with(collector.clone()).set_default();
+        ::tracing::info!("hello");
+        drop(subscriber);
+        let message = encode_message(&event_json(&collector.events()[0])).unwrap();
+
+        let receiving = EventCollector::default();
+        let receiver = EventReceiver::bind("127.0.0.1:0", &receiving).unwrap();
+        let mut stream = TcpStream::connect(receiver.local_addr()).unwrap();
+        stream.write_all(&encode_message(&json!({ "source": "raw" })).unwrap()).unwrap();
+        stream.write_all(&message).unwrap();
+        wait_for(&receiving, 1);
+
+        // returns only once the connection is closed and its thread is done.
+        receiver.stop();
+        assert_eq!(stream.read(&mut [0]).unwrap_or(0), 0);
+        let _ = stream.write_all(&message);
+        thread::sleep(POLL_INTERVAL);
+        assert_eq!(receiving.events().len(), 1);
+    }
+}
@@ -1,13 +1,35 @@
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
-use tracing::{Event, Level, Subscriber};
+use chrono::Local;
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
 #[cfg(feature = "log")]
 use tracing_log::NormalizeEvent;
-use tracing_subscriber::layer::Context;
+use tracing_subscriber::filter::Filtered;
+use tracing_subscriber::layer::{Context, Filter};
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 
+use super::directive::{Directives, ParseDirectiveError};
 use super::event::CollectedEvent;
+use super::export::{export, ExportFormat};
+use super::filter::CaptureFilter;
+use super::span::{
+    current_thread_name,
+    SpanData,
+    SpanEntry,
+    SpanInfo,
+    SpanRecord,
+    SpanStore,
+};
+use super::store::EventStore;
+use crate::parse::{ImportSummary, JsonReader, LogReader};
 
 #[derive(Clone, Debug)]
 pub enum AllowedTargets {
@@ -15,11 +37,115 @@ pub enum AllowedTargets {
     Selected(Vec<String>),
 }
 
+/// Number of events [`EventCollector::import`] reads before adding them.
+const IMPORT_BATCH_SIZE: usize = 1024;
+
+/// Decides which event is dropped once a collector with a capacity is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Drop the oldest retained event.
+    #[default]
+    DropOldest,
+    /// Drop the oldest event of the most verbose retained level, or the
+    /// incoming event if it is more verbose than everything retained.
+    DropLowestLevel,
+    /// Keep the retained events and drop the incoming one.
+    DropNewest,
+}
+
+/// Position of a reader within the collected events, see
+/// [`EventCollector::events_since`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventCursor {
+    pub(super) generation: u64,
+    pub(super) next_seq: u64,
+    pub(super) evictions: u64,
+}
+
+/// Changes since an [`EventCursor`], to be applied to a reader's own copy of
+/// the events in order: clear on `reset`, remove the `evicted` events, then
+/// append `events`.
+///
+/// Events are identified by sequence numbers, which increase in collection
+/// order and are never reused, not even after a clear.
+#[derive(Debug, Clone)]
+pub struct EventsSince {
+    /// The reader's events are stale (e.g. after a clear) and must be
+    /// replaced by `events`.
+    pub reset: bool,
+    /// Sequence numbers of the reader's events that are no longer retained,
+    /// in ascending order.
+    pub evicted: Vec<u64>,
+    pub events: Vec<Arc<CollectedEvent>>,
+    /// Sequence numbers of `events`.
+    pub seqs: Vec<u64>,
+    /// Cursor to pass on the next call.
+    pub cursor: EventCursor,
+}
+
+/// Number of events per level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelCounts([u64; 5]);
+
+impl LevelCounts {
+    pub fn get(&self, level: Level) -> u64 {
+        self.0[Self::index(level)]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    pub fn add(&mut self, other: &LevelCounts) {
+        for (count, other) in self.0.iter_mut().zip(other.0) {
+            *count += other;
+        }
+    }
+
+    pub(super) fn increment(&mut self, level: Level) {
+        self.0[Self::index(level)] += 1;
+    }
+
+    pub(super) fn decrement(&mut self, level: Level) {
+        self.0[Self::index(level)] -= 1;
+    }
+
+    pub(super) fn index(level: Level) -> usize {
+        match level {
+            Level::TRACE => 0,
+            Level::DEBUG => 1,
+            Level::INFO => 2,
+            Level::WARN => 3,
+            Level::ERROR => 4,
+        }
+    }
+}
+
+/// A [`Layer`] collecting events and span lifecycles for display.
+///
+/// Clones share the collected data and the capture configuration, so a clone
+/// can be given to the subscriber while another one is shown in the UI.
+///
+/// The level, target and directive configuration is reported to `tracing`
+/// through [`Layer::register_callsite`] and [`Layer::enabled`], which means
+/// that events the collector does not capture are disabled for the whole
+/// subscriber. A collector wrapped with [`Layer::with_filter`] only applies
+/// its configuration to the events it is given instead, so other layers still
+/// receive the events it drops.
+///
+/// `with_filter` can only notice this when called on the collector itself.
+/// When it is filtered in another way, e.g. with [`Filtered::new`], after
+/// [`Layer::boxed`] or inside another layer, call
+/// [`per_layer`](Self::per_layer) first, or the events it does not capture
+/// are disabled for the other layers too.
 #[derive(Debug, Clone)]
 pub struct EventCollector {
-    allowed_targets: AllowedTargets,
-    level: Level,
-    events: Arc<Mutex<Vec<CollectedEvent>>>,
+    filter: Arc<RwLock<CaptureFilter>>,
+    events: Arc<Mutex<EventStore>>,
+    spans: Arc<Mutex<SpanStore>>,
+    next_span_id: Arc<AtomicU64>,
+    /// Set by `with_filter` or `per_layer`, see above.
+    per_layer: bool,
 }
 
 impl EventCollector {
@@ -28,46 +154,254 @@ impl EventCollector {
     }
 
     pub fn with_level(self, level: Level) -> Self {
-        Self { level, ..self }
+        self.handle().set_level(level);
+        self
     }
 
     pub fn allowed_targets(self, allowed_targets: AllowedTargets) -> Self {
-        Self {
-            allowed_targets,
-            ..self
+        self.handle().set_allowed_targets(allowed_targets);
+        self
+    }
+
+    /// Only captures events enabled by the given `RUST_LOG`-style
+    /// directives, in addition to the level and target restrictions.
+    pub fn with_directives(self, directives: Directives) -> Self {
+        self.handle().set_directives(Some(directives));
+        self
+    }
+
+    /// Creates a collector using the directives in the environment variable
+    /// `var`, see [`Directives::from_env`].
+    pub fn from_env(var: &str) -> Result<Self, ParseDirectiveError> {
+        Ok(Self::default().with_directives(Directives::from_env(var)?))
+    }
+
+    /// Creates a collector using the directives in `RUST_LOG`.
+    pub fn from_default_env() -> Result<Self, ParseDirectiveError> {
+        Self::from_env("RUST_LOG")
+    }
+
+    /// Bounds the number of retained events, evicting events according to
+    /// the [`EvictionPolicy`] once `capacity` is reached. Closed spans are
+    /// bounded by the same capacity, dropping the first closed first.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        self.handle().set_capacity(Some(capacity));
+        self
+    }
+
+    pub fn with_eviction_policy(self, policy: EvictionPolicy) -> Self {
+        self.handle().set_eviction_policy(policy);
+        self
+    }
+
+    /// Only applies the capture configuration to the events given to the
+    /// collector, leaving the events of other layers alone. Needed when the
+    /// collector gets a per-layer filter other than through
+    /// [`Layer::with_filter`] on the collector itself.
+    pub fn per_layer(mut self) -> Self {
+        self.per_layer = true;
+        self
+    }
+
+    /// Returns a handle to change the capture configuration after the
+    /// collector has been installed.
+    pub fn handle(&self) -> CollectorHandle {
+        CollectorHandle {
+            filter: self.filter.clone(),
+            events: self.events.clone(),
+            spans: self.spans.clone(),
         }
     }
 
     pub fn events(&self) -> Vec<CollectedEvent> {
-        self.events.lock().unwrap().clone()
+        let events = self.events.lock().unwrap();
+        events.events().iter().map(|event| (**event).clone()).collect()
     }
 
-    pub fn clear(&self) {
-        let mut events = self.events.lock().unwrap();
-        *events = Vec::new();
-    }
-
-    fn collect(&self, event: CollectedEvent) {
-        if event.level <= self.level {
-            let should_collect = match self.allowed_targets {
-                AllowedTargets::All => true,
-                AllowedTargets::Selected(ref selection) => selection
-                    .iter()
-                    .any(|target| event.target.starts_with(target)),
-            };
-            if should_collect {
-                self.events.lock().unwrap().push(event);
+    /// Adds an event that was not collected from `tracing`, e.g. one read
+    /// from a log file. The capture filters don't apply, but the capacity
+    /// does.
+    pub fn insert(&self, event: CollectedEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    /// Adds several events at once, see [`insert`](Self::insert).
+    pub fn extend(&self, events: impl IntoIterator<Item = CollectedEvent>) {
+        self.push_all(events);
+    }
+
+    /// Adds `events`, returning the sequence numbers given to those retained.
+    fn push_all(&self, events: impl IntoIterator<Item = CollectedEvent>) -> Range<u64> {
+        let mut store = self.events.lock().unwrap();
+        let start = store.next_seq();
+        for event in events {
+            store.push(event);
+        }
+        start..store.next_seq()
+    }
+
+    /// Reads a log file with `reader` and adds its events in batches, so that
+    /// large files are not held in memory twice. Lines that can't be parsed
+    /// are skipped and reported in the summary. Events read before an I/O
+    /// error are kept.
+    pub fn import(
+        &self,
+        input: impl BufRead,
+        reader: &mut dyn LogReader,
+    ) -> io::Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut read = 0;
+        // sequence numbers of the added events, one range per batch.
+        let mut added = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            match reader.read_line(&line?) {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) => {}
+                Err(_) => summary.invalid_lines.push(index + 1),
+            }
+            if batch.len() >= IMPORT_BATCH_SIZE {
+                read += batch.len();
+                added.push(self.push_all(batch.drain(..)));
             }
         }
+        batch.extend(reader.flush());
+        read += batch.len();
+        added.push(self.push_all(batch));
+
+        summary.imported = self.events.lock().unwrap().count_retained(&added);
+        summary.dropped = read - summary.imported;
+        Ok(summary)
+    }
+
+    /// Reads a log file written by `tracing_subscriber::fmt().json()` and
+    /// adds its events, see [`JsonReader`].
+    pub fn import_json(&self, input: impl BufRead) -> io::Result<ImportSummary> {
+        self.import(input, &mut JsonReader::for_collector(self))
+    }
+
+    /// The counter span ids are allocated from.
+    pub(crate) fn span_ids(&self) -> Arc<AtomicU64> {
+        self.next_span_id.clone()
+    }
+
+    /// Writes all stored events to `writer`, see [`export`](super::export::export).
+    pub fn export(&self, format: ExportFormat, writer: impl Write) -> io::Result<()> {
+        let events = self.events.lock().unwrap().events();
+        export(events.iter().map(|event| &**event), format, writer)
+    }
+
+    /// Returns the events collected after `cursor` without copying them, so
+    /// that readers can keep their own copy up to date cheaply. Start with
+    /// `EventCursor::default()`.
+    pub fn events_since(&self, cursor: EventCursor) -> EventsSince {
+        self.events.lock().unwrap().since(cursor)
+    }
+
+    /// Number of events [`events_since`](Self::events_since) would return,
+    /// without copying them.
+    pub fn count_since(&self, cursor: EventCursor) -> usize {
+        self.events.lock().unwrap().count_since(cursor)
+    }
+
+    /// Recorded span lifecycles, ordered by creation.
+    pub fn spans(&self) -> Vec<SpanRecord> {
+        self.spans.lock().unwrap().spans().cloned().collect()
+    }
+
+    /// Number of retained events per target.
+    pub fn target_counts(&self) -> BTreeMap<String, LevelCounts> {
+        self.events.lock().unwrap().target_counts().clone()
+    }
+
+    /// Number of events dropped so far because the capacity was reached.
+    pub fn evicted(&self) -> u64 {
+        self.events.lock().unwrap().evicted()
+    }
+
+    /// Removes all events and closed spans.
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+        self.spans.lock().unwrap().clear();
+    }
+
+    fn update_span(&self, span_id: u64, f: impl FnOnce(&mut SpanRecord)) {
+        if let Some(record) = self.spans.lock().unwrap().get_mut(span_id) {
+            f(record);
+        }
+    }
+}
+
+/// Changes the capture configuration of a running [`EventCollector`], see
+/// [`EventCollector::handle`]. Changes apply to all clones of the collector.
+#[derive(Debug, Clone)]
+pub struct CollectorHandle {
+    filter: Arc<RwLock<CaptureFilter>>,
+    events: Arc<Mutex<EventStore>>,
+    spans: Arc<Mutex<SpanStore>>,
+}
+
+impl CollectorHandle {
+    pub fn level(&self) -> Level {
+        self.filter.read().unwrap().level
+    }
+
+    pub fn set_level(&self, level: Level) {
+        self.update_filter(|filter| filter.level = level);
+    }
+
+    pub fn allowed_targets(&self) -> AllowedTargets {
+        self.filter.read().unwrap().allowed_targets.clone()
+    }
+
+    pub fn set_allowed_targets(&self, allowed_targets: AllowedTargets) {
+        self.update_filter(|filter| filter.allowed_targets = allowed_targets);
+    }
+
+    pub fn directives(&self) -> Option<Directives> {
+        self.filter.read().unwrap().directives.clone()
+    }
+
+    /// Replaces the `RUST_LOG`-style directives, `None` removes them.
+    pub fn set_directives(&self, directives: Option<Directives>) {
+        self.update_filter(|filter| filter.directives = directives);
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.events.lock().unwrap().capacity()
+    }
+
+    /// Bounds the number of retained events and closed spans, `None` removes
+    /// the bound. Lowering the capacity evicts events right away.
+    pub fn set_capacity(&self, capacity: Option<usize>) {
+        self.events.lock().unwrap().set_capacity(capacity);
+        self.spans.lock().unwrap().set_capacity(capacity);
+    }
+
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.events.lock().unwrap().policy()
+    }
+
+    pub fn set_eviction_policy(&self, policy: EvictionPolicy) {
+        self.events.lock().unwrap().set_policy(policy);
+    }
+
+    /// Changes the filter and lets `tracing` re-evaluate which callsites are
+    /// enabled.
+    fn update_filter(&self, f: impl FnOnce(&mut CaptureFilter)) {
+        f(&mut self.filter.write().unwrap());
+        tracing::callsite::rebuild_interest_cache();
     }
 }
 
 impl Default for EventCollector {
     fn default() -> Self {
         Self {
-            allowed_targets: AllowedTargets::All,
-            events: Arc::new(Mutex::new(Vec::new())),
-            level: Level::TRACE, // capture everything by default.
+            filter: Arc::new(RwLock::new(CaptureFilter::default())),
+            events: Arc::new(Mutex::new(EventStore::default())),
+            spans: Arc::new(Mutex::new(SpanStore::default())),
+            next_span_id: Arc::new(AtomicU64::new(1)),
+            per_layer: false,
         }
     }
 }
@@ -76,7 +410,118 @@ impl<S> Layer<S> for EventCollector
 where
     S: Subscriber + for<'a> LookupSpan<'a>,
 {
-    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        if self.per_layer {
+            return Interest::sometimes();
+        }
+        self.filter.read().unwrap().interest(meta)
+    }
+
+    fn enabled(&self, meta: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        self.per_layer || self.filter.read().unwrap().callsite_enabled(meta)
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        if self.per_layer {
+            return true;
+        }
+
+        #[cfg(feature = "log")]
+        let normalized_meta = event.normalized_metadata();
+        #[cfg(feature = "log")]
+        let meta = normalized_meta.as_ref().unwrap_or_else(|| event.metadata());
+        #[cfg(not(feature = "log"))]
+        let meta = event.metadata();
+
+        self.filter.read().unwrap().static_enabled(meta) != Some(false)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        if self.per_layer {
+            return None;
+        }
+        Some(self.filter.read().unwrap().max_level())
+    }
+
+    fn with_filter<F>(mut self, filter: F) -> Filtered<Self, F, S>
+    where
+        Self: Sized,
+        F: Filter<S>,
+    {
+        self.per_layer = true;
+        Filtered::new(self, filter)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if self.per_layer && !self.filter.read().unwrap().callsite_enabled(attrs.metadata()) {
+            return;
+        }
+        let Some(span) = ctx.span(id) else { return };
+        let parent = span.parent().and_then(|parent| {
+            parent.extensions().get::<SpanData>().map(|data| data.0.id)
+        });
+
+        let mut extensions = span.extensions_mut();
+        if extensions.get_mut::<SpanData>().is_some() {
+            return;
+        }
+        let span_id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        let info = Arc::new(SpanInfo::new(span_id, attrs));
+        extensions.insert(SpanData(info.clone()));
+        drop(extensions);
+
+        self.spans.lock().unwrap().insert(SpanRecord {
+            info,
+            parent,
+            thread: current_thread_name(),
+            created: Local::now(),
+            closed: None,
+            entries: Vec::new(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanData(info)) = extensions.get_mut::<SpanData>() {
+            Arc::make_mut(info).record(values);
+            let info = info.clone();
+            self.update_span(info.id, |record| record.info = info);
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span_id) = span_id(id, &ctx) else { return };
+        self.update_span(span_id, |record| {
+            record.entries.push(SpanEntry {
+                thread: current_thread_name(),
+                entered: Local::now(),
+                exited: None,
+            });
+        });
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span_id) = span_id(id, &ctx) else { return };
+        let thread = current_thread_name();
+        self.update_span(span_id, |record| {
+            if let Some(entry) = record
+                .entries
+                .iter_mut()
+                .rev()
+                .find(|entry| entry.exited.is_none() && entry.thread == thread)
+            {
+                entry.exited = Some(Local::now());
+            }
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span_id) = span_id(&id, &ctx) else { return };
+        self.spans.lock().unwrap().close(span_id, Local::now());
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
         #[cfg(feature = "log")]
         let normalized_meta = event.normalized_metadata();
         #[cfg(feature = "log")]
@@ -84,6 +529,110 @@ where
         #[cfg(not(feature = "log"))]
         let meta = event.metadata();
 
-        self.collect(CollectedEvent::new(event, meta));
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .filter_map(|span| {
+                        span.extensions().get::<SpanData>().map(|data| data.0.clone())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        // checked before recording the fields, which is the expensive part.
+        if !self.filter.read().unwrap().enabled(meta, &spans) {
+            return;
+        }
+
+        let mut collected = CollectedEvent::new(event, meta);
+        collected.spans = spans;
+        self.events.lock().unwrap().push(collected);
+    }
+}
+
+fn span_id<S>(id: &Id, ctx: &Context<'_, S>) -> Option<u64>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let span = ctx.span(id)?;
+    let extensions = span.extensions();
+    extensions.get::<SpanData>().map(|data| data.0.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    use super::*;
+
+    /// Counts the events it is given.
+    #[derive(Clone, Default)]
+    struct Counter(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for Counter {
+        fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn emit() {
+        ::tracing::debug!("debug");
+        ::tracing::info!("info");
+        ::tracing::warn!("warn");
+        ::tracing::error!("error");
+    }
+
+    fn levels(collector: &EventCollector) -> Vec<Level> {
+        collector.events().iter().map(|event| event.level).collect()
+    }
+
+    #[test]
+    fn filtered_collector_leaves_other_layers_alone() {
+        let collector = EventCollector::default().with_level(Level::WARN);
+        let counter = Counter::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(collector.clone().with_filter(LevelFilter::INFO))
+            .with(counter.clone())
+            .set_default();
+        emit();
+        drop(subscriber);
+        assert_eq!(levels(&collector), [Level::WARN, Level::ERROR]);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn per_layer_collector_leaves_other_layers_alone() {
+        let collector = EventCollector::default().with_level(Level::WARN);
+        let boxed = EventCollector::default().with_level(Level::ERROR);
+        let counter = Counter::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(Filtered::new(collector.clone().per_layer(), LevelFilter::INFO))
+            .with(boxed.clone().per_layer().boxed().with_filter(LevelFilter::TRACE))
+            .with(counter.clone())
+            .set_default();
+        emit();
+        drop(subscriber);
+        assert_eq!(levels(&collector), [Level::WARN, Level::ERROR]);
+        assert_eq!(levels(&boxed), [Level::ERROR]);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn unfiltered_collector_filters_the_subscriber() {
+        let collector = EventCollector::default().with_level(Level::WARN);
+        let counter = Counter::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(collector.clone())
+            .with(counter.clone())
+            .set_default();
+        emit();
+        drop(subscriber);
+        assert_eq!(levels(&collector), [Level::WARN, Level::ERROR]);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 2);
     }
 }
@@ -0,0 +1,436 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use tracing::level_filters::LevelFilter;
+use tracing::{Level, Metadata};
+
+use super::event::CollectedEvent;
+use super::field::FieldValue;
+use super::span::SpanInfo;
+
+/// A list of `RUST_LOG`-style filter directives, e.g.
+/// `my_crate=debug,hyper=warn,my_crate::net[span{id=3}]=trace`.
+///
+/// The syntax follows `tracing_subscriber::EnvFilter`: each comma-separated
+/// directive is `target[span{field=value}]=level`, where every part is
+/// optional. A bare level applies to all targets and a bare target enables all
+/// levels for it. An event is captured if the most specific directive matching
+/// it enables its level; events matching no directive are not captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directives(Vec<Directive>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Prefix the event target must start with.
+    pub target: Option<String>,
+    /// Name of a span the event must be inside of.
+    pub span: Option<String>,
+    /// Fields the matching span must have, optionally with a given value.
+    pub fields: Vec<FieldMatch>,
+    pub level: LevelFilter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMatch {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectiveError {
+    directive: String,
+    reason: &'static str,
+}
+
+impl Directives {
+    /// Reads the directives from the environment variable `var`. An unset or
+    /// empty variable enables `ERROR` for everything, like `EnvFilter`.
+    pub fn from_env(var: &str) -> Result<Self, ParseDirectiveError> {
+        std::env::var(var).unwrap_or_default().parse()
+    }
+
+    pub fn directives(&self) -> &[Directive] {
+        &self.0
+    }
+
+    /// Whether the event is enabled, taking its spans into account.
+    pub fn enabled(&self, event: &CollectedEvent) -> bool {
+        self.enabled_in(&event.target, event.level, &event.spans)
+    }
+
+    pub fn enabled_in(
+        &self,
+        target: &str,
+        level: Level,
+        spans: &[Arc<SpanInfo>],
+    ) -> bool {
+        self.0
+            .iter()
+            .filter(|directive| directive.matches(target, spans))
+            .max_by_key(|directive| directive.specificity())
+            .is_some_and(|directive| directive.level >= level)
+    }
+
+    /// Decides from metadata alone whether a callsite is enabled. Returns
+    /// `None` for events whose outcome depends on the spans they are emitted
+    /// in. Spans named by a span directive are always enabled, so that events
+    /// inside them can be matched.
+    pub(crate) fn static_enabled(&self, meta: &Metadata<'_>) -> Option<bool> {
+        let target = meta.target();
+        let level = *meta.level();
+        let enabled = self
+            .0
+            .iter()
+            .filter(|directive| {
+                !directive.is_dynamic() && directive.matches_target(target)
+            })
+            .max_by_key(|directive| directive.specificity())
+            .is_some_and(|directive| directive.level >= level);
+
+        let mut dynamic = self.0.iter().filter(|directive| directive.is_dynamic());
+        if meta.is_span() {
+            let named = dynamic.any(|directive| {
+                directive
+                    .span
+                    .as_ref()
+                    .is_none_or(|name| name == meta.name())
+            });
+            return Some(enabled || named);
+        }
+        let depends_on_spans = dynamic.any(|directive| {
+            directive.matches_target(target) && (directive.level >= level) != enabled
+        });
+        (!depends_on_spans).then_some(enabled)
+    }
+
+    /// The most verbose level any directive enables.
+    pub fn max_level(&self) -> LevelFilter {
+        self.0
+            .iter()
+            .map(|directive| directive.level)
+            .max()
+            .unwrap_or(LevelFilter::OFF)
+    }
+}
+
+impl Directive {
+    fn is_dynamic(&self) -> bool {
+        self.span.is_some() || !self.fields.is_empty()
+    }
+
+    fn specificity(&self) -> (bool, usize, usize) {
+        (
+            self.is_dynamic(),
+            self.target.as_ref().map_or(0, String::len),
+            self.fields.len(),
+        )
+    }
+
+    fn matches_target(&self, target: &str) -> bool {
+        self.target
+            .as_ref()
+            .is_none_or(|prefix| target.starts_with(prefix.as_str()))
+    }
+
+    fn matches(&self, target: &str, spans: &[Arc<SpanInfo>]) -> bool {
+        if !self.matches_target(target) {
+            return false;
+        }
+        if !self.is_dynamic() {
+            return true;
+        }
+        spans.iter().any(|span| {
+            self.span.as_ref().is_none_or(|name| *name == span.name)
+                && self.fields.iter().all(|field| field.matches(span))
+        })
+    }
+}
+
+impl FieldMatch {
+    fn matches(&self, span: &SpanInfo) -> bool {
+        let Some(value) = span.fields.get(&self.name) else {
+            return false;
+        };
+        let Some(expected) = &self.value else {
+            return true;
+        };
+        match value {
+            FieldValue::Str(value) => {
+                value == expected.trim_matches('"') || value == expected
+            }
+            value => value.to_string() == *expected,
+        }
+    }
+}
+
+impl FromStr for Directives {
+    type Err = ParseDirectiveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let directives = split_top_level(s, ',')
+            .into_iter()
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Directive>, _>>()?;
+
+        if directives.is_empty() {
+            return Ok(Self(vec![Directive {
+                target: None,
+                span: None,
+                fields: Vec::new(),
+                level: LevelFilter::ERROR,
+            }]));
+        }
+        Ok(Self(directives))
+    }
+}
+
+impl FromStr for Directive {
+    type Err = ParseDirectiveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| ParseDirectiveError {
+            directive: s.to_owned(),
+            reason,
+        };
+
+        let (selector, level) = match split_top_level(s, '=').as_slice() {
+            [selector] => match parse_level(selector) {
+                Some(level) if !selector.contains('[') => ("", level),
+                _ => (*selector, LevelFilter::TRACE),
+            },
+            [selector, level] => (
+                *selector,
+                parse_level(level).ok_or_else(|| error("invalid level"))?,
+            ),
+            _ => return Err(error("expected a single `=` before the level")),
+        };
+
+        let (target, span_selector) = match selector.split_once('[') {
+            Some((target, rest)) => {
+                let span_selector = rest
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("missing closing `]`"))?;
+                (target, Some(span_selector))
+            }
+            None => (selector, None),
+        };
+        if target.contains([']', '{', '}']) {
+            return Err(error("unexpected bracket in target"));
+        }
+
+        let mut span = None;
+        let mut fields = Vec::new();
+        if let Some(span_selector) = span_selector {
+            let (name, field_list) = match span_selector.split_once('{') {
+                Some((name, rest)) => {
+                    let field_list = rest
+                        .strip_suffix('}')
+                        .ok_or_else(|| error("missing closing `}`"))?;
+                    (name, Some(field_list))
+                }
+                None => (span_selector, None),
+            };
+            if !name.is_empty() {
+                span = Some(name.trim().to_owned());
+            }
+            for field in field_list.into_iter().flat_map(|list| list.split(',')) {
+                let field = field.trim();
+                if field.is_empty() {
+                    continue;
+                }
+                fields.push(match field.split_once('=') {
+                    Some((name, value)) => FieldMatch {
+                        name: name.trim().to_owned(),
+                        value: Some(value.trim().to_owned()),
+                    },
+                    None => FieldMatch {
+                        name: field.to_owned(),
+                        value: None,
+                    },
+                });
+            }
+        }
+
+        let target = target.trim();
+        Ok(Self {
+            target: (!target.is_empty()).then(|| target.to_owned()),
+            span,
+            fields,
+            level,
+        })
+    }
+}
+
+impl Display for Directives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, directive) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            Display::fmt(directive, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(target) = &self.target {
+            f.write_str(target)?;
+        }
+        if self.is_dynamic() {
+            f.write_str("[")?;
+            if let Some(span) = &self.span {
+                f.write_str(span)?;
+            }
+            if !self.fields.is_empty() {
+                let fields = self
+                    .fields
+                    .iter()
+                    .map(|field| match &field.value {
+                        Some(value) => format!("{}={}", field.name, value),
+                        None => field.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                write!(f, "{{{}}}", fields)?;
+            }
+            f.write_str("]")?;
+        }
+        if self.target.is_some() || self.is_dynamic() {
+            f.write_str("=")?;
+        }
+        write!(f, "{}", self.level.to_string().to_lowercase())
+    }
+}
+
+impl Display for ParseDirectiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter directive `{}`: {}", self.directive, self.reason)
+    }
+}
+
+impl Error for ParseDirectiveError {}
+
+fn parse_level(s: &str) -> Option<LevelFilter> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "off" | "0" => Some(LevelFilter::OFF),
+        "error" | "1" => Some(LevelFilter::ERROR),
+        "warn" | "2" => Some(LevelFilter::WARN),
+        "info" | "3" => Some(LevelFilter::INFO),
+        "debug" | "4" => Some(LevelFilter::DEBUG),
+        "trace" | "5" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+/// Splits `s` on `separator`, ignoring separators inside `[...]` or `{...}`.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn span(name: &str, fields: &[(&str, FieldValue)]) -> Arc<SpanInfo> {
+        Arc::new(SpanInfo {
+            id: 1,
+            name: name.to_owned(),
+            target: "my_crate::net".to_owned(),
+            level: Level::INFO,
+            fields: fields
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), value.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        for s in [
+            "my_crate=debug,hyper=warn,my_crate::net[span{id=3}]=trace",
+            "info,my_crate=off",
+            "[request]=debug,my_crate[{user}]=trace",
+        ] {
+            let directives: Directives = s.parse().unwrap();
+            assert_eq!(directives.to_string(), s);
+            assert_eq!(directives.to_string().parse::<Directives>().unwrap(), directives);
+        }
+
+        let directives: Directives = " my_crate = DEBUG , ,hyper=2".parse().unwrap();
+        assert_eq!(directives.to_string(), "my_crate=debug,hyper=warn");
+        let directive: Directive = "my_crate".parse().unwrap();
+        assert_eq!(directive.level, LevelFilter::TRACE);
+    }
+
+    #[test]
+    fn empty_enables_errors() {
+        for s in ["", " ", ","] {
+            let directives: Directives = s.parse().unwrap();
+            assert_eq!(directives.to_string(), "error");
+            assert!(directives.enabled_in("my_crate", Level::ERROR, &[]));
+            assert!(!directives.enabled_in("my_crate", Level::WARN, &[]));
+        }
+    }
+
+    #[test]
+    fn most_specific_wins() {
+        let directives: Directives =
+            "my_crate=debug,hyper=warn,my_crate::net[span{id=3}]=trace"
+                .parse()
+                .unwrap();
+        assert_eq!(directives.max_level(), LevelFilter::TRACE);
+
+        assert!(directives.enabled_in("my_crate::net", Level::DEBUG, &[]));
+        assert!(!directives.enabled_in("my_crate::net", Level::TRACE, &[]));
+        let matching = [span("span", &[("id", FieldValue::U64(3))])];
+        assert!(directives.enabled_in("my_crate::net", Level::TRACE, &matching));
+        let other = [span("span", &[("id", FieldValue::U64(4))])];
+        assert!(!directives.enabled_in("my_crate::net", Level::TRACE, &other));
+        // the span directive only applies below its target.
+        assert!(!directives.enabled_in("my_crate::db", Level::TRACE, &matching));
+
+        assert!(directives.enabled_in("hyper::client", Level::WARN, &[]));
+        assert!(!directives.enabled_in("hyper::client", Level::INFO, &[]));
+        assert!(!directives.enabled_in("other", Level::ERROR, &[]));
+
+        let directives: Directives = "warn,my_crate=info,my_crate::net=off".parse().unwrap();
+        assert!(directives.enabled_in("other", Level::WARN, &[]));
+        assert!(directives.enabled_in("my_crate::db", Level::INFO, &[]));
+        assert!(!directives.enabled_in("my_crate::net", Level::ERROR, &[]));
+    }
+
+    #[test]
+    fn invalid() {
+        for s in ["a=b=c", "my_crate=verbose", "my_crate[span", "my_crate[span{id]", "a}=info"] {
+            assert!(s.parse::<Directives>().is_err(), "{}", s);
+        }
+        let err = "a=b=c".parse::<Directives>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid filter directive `a=b=c`: expected a single `=` before the level"
+        );
+    }
+}
@@ -1,16 +1,30 @@
 use std::collections::BTreeMap;
-use std::fmt::Debug;
+use std::sync::Arc;
 
 use chrono::{DateTime, Local};
-use tracing::field::{Field, Visit};
 use tracing::{Event, Metadata};
 
+use super::field::{FieldValue, FieldVisitor};
+use super::span::SpanInfo;
+
 #[derive(Debug, Clone)]
 pub struct CollectedEvent {
     pub target: String,
     pub level: tracing::Level,
-    pub fields: BTreeMap<String, String>,
+    pub fields: BTreeMap<String, FieldValue>,
     pub time: DateTime<Local>,
+    /// Spans the event was emitted in, from the root to the innermost span.
+    pub spans: Vec<Arc<SpanInfo>>,
+    /// Name of the callsite, e.g. `event src/main.rs:42`.
+    pub name: String,
+    pub module_path: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub thread_id: Option<String>,
+    pub thread_name: Option<String>,
+    /// Label of the process the event was received from, see
+    /// [`remote`](crate::remote). `None` for events of this process.
+    pub source: Option<String>,
 }
 
 impl CollectedEvent {
@@ -18,20 +32,35 @@ impl CollectedEvent {
         let mut fields = BTreeMap::new();
         event.record(&mut FieldVisitor(&mut fields));
 
+        let thread = std::thread::current();
+
         CollectedEvent {
             level: meta.level().to_owned(),
             time: Local::now(),
             target: meta.target().to_owned(),
             fields,
+            spans: Vec::new(),
+            name: meta.name().to_owned(),
+            module_path: meta.module_path().map(str::to_owned),
+            file: meta.file().map(str::to_owned),
+            line: meta.line(),
+            thread_id: Some(format!("{:?}", thread.id())),
+            thread_name: thread.name().map(str::to_owned),
+            source: None,
         }
     }
-}
 
-struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+    /// Source location as `file:line`, if known.
+    pub fn location(&self) -> Option<String> {
+        let file = self.file.as_ref()?;
+        Some(match self.line {
+            Some(line) => format!("{}:{}", file, line),
+            None => file.clone(),
+        })
+    }
 
-impl<'a> Visit for FieldVisitor<'a> {
-    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
-        self.0
-            .insert(field.name().to_string(), format!("{:?}", value));
+    /// Thread name, falling back to the thread id for unnamed threads.
+    pub fn thread(&self) -> Option<&str> {
+        self.thread_name.as_deref().or(self.thread_id.as_deref())
     }
 }
@@ -0,0 +1,317 @@
+use std::io::{self, Write};
+
+use chrono::SecondsFormat;
+use serde_json::{Map, Value};
+
+use super::event::CollectedEvent;
+use super::field::FieldValue;
+use super::span::{format_span_path, SpanInfo};
+
+/// File formats events can be exported to, see [`export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// One JSON object per line, laid out like the output of
+    /// `tracing_subscriber::fmt().json()`.
+    JsonLines,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Plain text like the output of `tracing_subscriber::fmt()`.
+    Text,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] =
+        [ExportFormat::JsonLines, ExportFormat::Csv, ExportFormat::Text];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "JSON Lines",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Text => "Text",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Text => "log",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "application/jsonl",
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Text => "text/plain",
+        }
+    }
+}
+
+/// Writes `events` to `writer` in the given format and flushes it.
+pub fn export<'a, W: Write>(
+    events: impl IntoIterator<Item = &'a CollectedEvent>,
+    format: ExportFormat,
+    mut writer: W,
+) -> io::Result<()> {
+    if format == ExportFormat::Csv {
+        writeln!(
+            writer,
+            "time,level,target,spans,thread,location,message,fields,source"
+        )?;
+    }
+    for event in events {
+        match format {
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut writer, &event_json(event))?;
+                writeln!(writer)?;
+            }
+            ExportFormat::Csv => write_csv(&mut writer, event)?,
+            ExportFormat::Text => write_text(&mut writer, event)?,
+        }
+    }
+    writer.flush()
+}
+
+fn timestamp(event: &CollectedEvent) -> String {
+    event.time.to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+pub(crate) fn event_json(event: &CollectedEvent) -> Value {
+    let mut object = Map::new();
+    object.insert("timestamp".into(), timestamp(event).into());
+    object.insert("level".into(), event.level.as_str().into());
+    object.insert("fields".into(), fields_json(&event.fields).into());
+    object.insert("target".into(), event.target.clone().into());
+    if let Some(file) = &event.file {
+        object.insert("filename".into(), file.clone().into());
+    }
+    if let Some(line) = event.line {
+        object.insert("line_number".into(), line.into());
+    }
+    if let Some(span) = event.spans.last() {
+        object.insert("span".into(), span_json(span));
+        let spans = event.spans.iter().map(|span| span_json(span)).collect();
+        object.insert("spans".into(), Value::Array(spans));
+    }
+    if let Some(thread_name) = &event.thread_name {
+        object.insert("threadName".into(), thread_name.clone().into());
+    }
+    if let Some(thread_id) = &event.thread_id {
+        object.insert("threadId".into(), thread_id.clone().into());
+    }
+    if let Some(source) = &event.source {
+        object.insert("source".into(), source.clone().into());
+    }
+    Value::Object(object)
+}
+
+fn span_json(span: &SpanInfo) -> Value {
+    let mut object = fields_json(&span.fields);
+    object.insert("name".into(), span.name.clone().into());
+    Value::Object(object)
+}
+
+fn fields_json<'a>(
+    fields: impl IntoIterator<Item = (&'a String, &'a FieldValue)>,
+) -> Map<String, Value> {
+    fields
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                FieldValue::I64(value) => (*value).into(),
+                FieldValue::U64(value) => (*value).into(),
+                FieldValue::F64(value) => (*value).into(),
+                FieldValue::Bool(value) => (*value).into(),
+                value => value.to_string().into(),
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+/// Formats the fields other than the message like `tracing_subscriber::fmt`,
+/// e.g. `user="alice" attempt=2`.
+fn format_fields(event: &CollectedEvent) -> String {
+    event
+        .fields
+        .iter()
+        .filter(|(key, _)| *key != "message" && !key.starts_with("log."))
+        .map(|(key, value)| match value {
+            FieldValue::Str(value) => format!("{}={:?}", key, value),
+            value => format!("{}={}", key, value),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn message(event: &CollectedEvent) -> String {
+    event
+        .fields
+        .get("message")
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+fn write_text(writer: &mut impl Write, event: &CollectedEvent) -> io::Result<()> {
+    write!(writer, "{} {:>5} ", timestamp(event), event.level.as_str())?;
+    if !event.spans.is_empty() {
+        write!(writer, "{}: ", format_span_path(&event.spans, true))?;
+    }
+    write!(writer, "{}: {}", event.target, message(event))?;
+    let fields = format_fields(event);
+    if !fields.is_empty() {
+        write!(writer, " {}", fields)?;
+    }
+    writeln!(writer)
+}
+
+fn write_csv(writer: &mut impl Write, event: &CollectedEvent) -> io::Result<()> {
+    let values = [
+        timestamp(event),
+        event.level.as_str().to_owned(),
+        event.target.clone(),
+        format_span_path(&event.spans, true),
+        event.thread().unwrap_or_default().to_owned(),
+        event.location().unwrap_or_default(),
+        message(event),
+        format_fields(event),
+        event.source.clone().unwrap_or_default(),
+    ];
+    let row = values
+        .iter()
+        .map(|value| csv_escape(value))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", row)
+}
+
+/// Quotes a CSV value if it contains a separator, quote or line break.
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use chrono::{Local, TimeZone};
+    use tracing::Level;
+
+    use super::*;
+    use crate::parse::{JsonReader, LogReader};
+
+    fn event() -> CollectedEvent {
+        let span = SpanInfo {
+            id: 1,
+            name: "request".to_owned(),
+            target: "my_app::net".to_owned(),
+            level: Level::INFO,
+            fields: [("id".to_owned(), FieldValue::I64(3))].into(),
+        };
+        CollectedEvent {
+            target: "my_app::net".to_owned(),
+            level: Level::WARN,
+            fields: [
+                ("message", FieldValue::Str("slow, \"really\"\nslow".to_owned())),
+                ("user", FieldValue::Str("alice".to_owned())),
+                ("attempt", FieldValue::I64(2)),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect::<BTreeMap<_, _>>(),
+            time: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            spans: vec![Arc::new(span)],
+            name: "event src/net.rs:42".to_owned(),
+            module_path: Some("my_app::net".to_owned()),
+            file: Some("src/net.rs".to_owned()),
+            line: Some(42),
+            thread_id: Some("ThreadId(2)".to_owned()),
+            thread_name: Some("worker".to_owned()),
+            source: Some("server[42]".to_owned()),
+        }
+    }
+
+    fn exported(event: &CollectedEvent, format: ExportFormat) -> String {
+        let mut output = Vec::new();
+        export([event], format, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn escapes_csv_values() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape(""), "");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("one\ntwo"), "\"one\ntwo\"");
+        assert_eq!(csv_escape("one\r\ntwo"), "\"one\r\ntwo\"");
+    }
+
+    #[test]
+    fn writes_csv() {
+        let event = event();
+        let csv = exported(&event, ExportFormat::Csv);
+        let (header, row) = csv.split_once('\n').unwrap();
+        assert_eq!(
+            header,
+            "time,level,target,spans,thread,location,message,fields,source"
+        );
+        assert_eq!(
+            row,
+            format!(
+                "{},WARN,my_app::net,request{{id=3}},worker,src/net.rs:42,\
+                 \"slow, \"\"really\"\"\nslow\",\"attempt=2 user=\"\"alice\"\"\",server[42]\n",
+                timestamp(&event)
+            )
+        );
+    }
+
+    #[test]
+    fn writes_text() {
+        let mut event = event();
+        event.fields.insert("message".to_owned(), FieldValue::Str("slow".to_owned()));
+        let text = exported(&event, ExportFormat::Text);
+        assert!(timestamp(&event).starts_with("2024-05-01T12:00:00.000000"));
+        assert_eq!(
+            text,
+            format!(
+                "{}  WARN request{{id=3}}: my_app::net: slow attempt=2 user=\"alice\"\n",
+                timestamp(&event)
+            )
+        );
+
+        event.spans.clear();
+        event.level = Level::ERROR;
+        event.fields.retain(|key, _| key == "message");
+        let text = exported(&event, ExportFormat::Text);
+        assert_eq!(text, format!("{} ERROR my_app::net: slow\n", timestamp(&event)));
+    }
+
+    #[test]
+    fn writes_json_lines_readable_by_json_reader() {
+        let event = event();
+        let jsonl = exported(&event, ExportFormat::JsonLines);
+        assert_eq!(jsonl.lines().count(), 1);
+
+        let read = JsonReader::new().read_line(jsonl.trim_end()).unwrap().unwrap();
+        assert_eq!(read.time, event.time);
+        assert_eq!(read.level, event.level);
+        assert_eq!(read.target, event.target);
+        assert_eq!(read.fields, event.fields);
+        assert_eq!(read.name, event.name);
+        assert_eq!(read.file, event.file);
+        assert_eq!(read.line, event.line);
+        assert_eq!(read.thread_id, event.thread_id);
+        assert_eq!(read.thread_name, event.thread_name);
+        assert_eq!(read.source, event.source);
+        assert_eq!(read.spans.len(), 1);
+        assert_eq!(read.spans[0].name, "request");
+        assert_eq!(read.spans[0].fields, event.spans[0].fields);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+
+use tracing::field::{Field, Visit};
+
+/// A recorded field value, keeping the type it was recorded with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    /// An error and the chain of its sources, outermost first.
+    Error {
+        message: String,
+        sources: Vec<String>,
+    },
+    /// Any other value, formatted with its `Debug` implementation.
+    Debug(String),
+}
+
+impl FieldValue {
+    /// The value as a string slice, for string-like values.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(value) | Self::Debug(value) => Some(value),
+            Self::Error { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// The value as a number, for numeric values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::I64(value) => Some(value as f64),
+            Self::U64(value) => Some(value as f64),
+            Self::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::I64(value) => Display::fmt(value, f),
+            Self::U64(value) => Display::fmt(value, f),
+            Self::F64(value) => Display::fmt(value, f),
+            Self::Bool(value) => Display::fmt(value, f),
+            Self::Str(value) | Self::Debug(value) => f.write_str(value),
+            Self::Error { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+pub(crate) struct FieldVisitor<'a>(pub &'a mut BTreeMap<String, FieldValue>);
+
+impl FieldVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: FieldValue) {
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, FieldValue::F64(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, FieldValue::U64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, FieldValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, FieldValue::Str(value.to_owned()));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn Error + 'static)) {
+        let mut sources = Vec::new();
+        let mut source = value.source();
+        while let Some(error) = source {
+            sources.push(error.to_string());
+            source = error.source();
+        }
+        self.insert(
+            field,
+            FieldValue::Error {
+                message: value.to_string(),
+                sources,
+            },
+        );
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, FieldValue::Debug(format!("{:?}", value)));
+    }
+}
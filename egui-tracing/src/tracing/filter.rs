@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{Level, Metadata};
+
+use super::collector::AllowedTargets;
+use super::directive::Directives;
+use super::span::SpanInfo;
+
+/// Which events and spans an [`EventCollector`](super::EventCollector)
+/// captures. The level applies to events and spans, the allowed targets only
+/// to events, and the directives as described in [`Directives`].
+#[derive(Debug, Clone)]
+pub(crate) struct CaptureFilter {
+    pub allowed_targets: AllowedTargets,
+    pub level: Level,
+    pub directives: Option<Directives>,
+}
+
+impl Default for CaptureFilter {
+    fn default() -> Self {
+        Self {
+            allowed_targets: AllowedTargets::All,
+            level: Level::TRACE, // capture everything by default.
+            directives: None,
+        }
+    }
+}
+
+impl CaptureFilter {
+    pub fn interest(&self, meta: &Metadata<'_>) -> Interest {
+        #[cfg(feature = "log")]
+        if is_log_callsite(meta) {
+            // all `log` records share a few callsites per level, so their
+            // targets can only be checked once the event is normalized.
+            return if self.max_level() >= *meta.level() {
+                Interest::sometimes()
+            } else {
+                Interest::never()
+            };
+        }
+
+        match self.static_enabled(meta) {
+            Some(true) => Interest::always(),
+            Some(false) => Interest::never(),
+            None => Interest::sometimes(),
+        }
+    }
+
+    /// Whether a callsite may be captured at all.
+    pub fn callsite_enabled(&self, meta: &Metadata<'_>) -> bool {
+        !self.interest(meta).is_never()
+    }
+
+    /// Decides from metadata alone whether a callsite is captured, `None` if
+    /// that depends on the spans an event is emitted in.
+    pub fn static_enabled(&self, meta: &Metadata<'_>) -> Option<bool> {
+        if *meta.level() > self.level {
+            return Some(false);
+        }
+        if meta.is_event() && !self.target_allowed(meta.target()) {
+            return Some(false);
+        }
+        match &self.directives {
+            Some(directives) => directives.static_enabled(meta),
+            None => Some(true),
+        }
+    }
+
+    /// Whether an event is captured, given the spans it was emitted in.
+    pub fn enabled(&self, meta: &Metadata<'_>, spans: &[Arc<SpanInfo>]) -> bool {
+        self.static_enabled(meta).unwrap_or_else(|| {
+            self.directives.as_ref().is_some_and(|directives| {
+                directives.enabled_in(meta.target(), *meta.level(), spans)
+            })
+        })
+    }
+
+    pub fn max_level(&self) -> LevelFilter {
+        let level = LevelFilter::from_level(self.level);
+        match &self.directives {
+            Some(directives) => level.min(directives.max_level()),
+            None => level,
+        }
+    }
+
+    fn target_allowed(&self, target: &str) -> bool {
+        match self.allowed_targets {
+            AllowedTargets::All => true,
+            AllowedTargets::Selected(ref selection) => selection
+                .iter()
+                .any(|allowed| target.starts_with(allowed.as_str())),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+fn is_log_callsite(meta: &Metadata<'_>) -> bool {
+    meta.is_event() && meta.fields().field("log.target").is_some()
+}
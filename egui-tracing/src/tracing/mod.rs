@@ -1,5 +1,24 @@
 pub mod collector;
+pub mod directive;
 pub mod event;
+pub mod export;
+pub mod field;
+mod filter;
+pub mod query;
+pub mod span;
+mod store;
 
-pub use collector::EventCollector;
+pub use collector::{
+    CollectorHandle,
+    EventCollector,
+    EventCursor,
+    EventsSince,
+    EvictionPolicy,
+    LevelCounts,
+};
+pub use directive::Directives;
 pub use event::CollectedEvent;
+pub use export::ExportFormat;
+pub use field::FieldValue;
+pub use query::Query;
+pub use span::SpanInfo;
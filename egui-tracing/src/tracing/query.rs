@@ -0,0 +1,655 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use chrono::{
+    DateTime,
+    Local,
+    NaiveDate,
+    NaiveDateTime,
+    NaiveTime,
+    TimeZone,
+};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use tracing::Level;
+
+use super::event::CollectedEvent;
+use super::field::FieldValue;
+
+/// A filter expression over collected events, e.g.
+/// `level>=warn and target:my_app::* and user_id=42 and message~"timeout"`.
+///
+/// A query is made of conditions `key op value` combined with `and`, `or`,
+/// `not` and parentheses; conditions next to each other are combined with
+/// `and`. Keys are `level`, `target`, `time`, `span`, `thread`, `module`,
+/// `file`, `source` or the name of an event field such as `message`.
+/// Operators:
+///
+/// - `=`, `!=`, `<`, `<=`, `>`, `>=` compare levels by severity, times,
+///   numbers and text
+/// - `:` and `!:` match a glob pattern, `~` and `!~` a regular expression
+///
+/// A key on its own matches events having that field. Values containing
+/// spaces or parentheses can be quoted with `"`. Times are given as RFC 3339,
+/// `YYYY-MM-DD HH:MM:SS`, a date, or a time of day compared on any day.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQueryError {
+    /// Byte offset in the query at which parsing failed.
+    pub position: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Condition(Condition),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    key: Key,
+    /// `None` only checks that the key is present.
+    test: Option<Test>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key {
+    Level,
+    Target,
+    Time,
+    Span,
+    Thread,
+    Module,
+    File,
+    Source,
+    Field(String),
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    Compare(CmpOp, Operand),
+    Glob { negated: bool, matcher: GlobMatcher },
+    Regex { negated: bool, regex: Regex },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Level(Level),
+    DateTime(DateTime<Local>),
+    TimeOfDay(NaiveTime),
+    Number(f64, String),
+    Text(String),
+}
+
+/// The value of an event a condition is evaluated against.
+enum Subject<'a> {
+    Level(Level),
+    Time(DateTime<Local>),
+    Text(&'a str),
+    Field(&'a FieldValue),
+    /// Matches if any of the values does, e.g. the names of the spans.
+    Any(Vec<&'a str>),
+}
+
+impl Query {
+    pub fn matches(&self, event: &CollectedEvent) -> bool {
+        self.expr.matches(event)
+    }
+}
+
+impl Expr {
+    fn matches(&self, event: &CollectedEvent) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(event) && right.matches(event),
+            Expr::Or(left, right) => left.matches(event) || right.matches(event),
+            Expr::Not(expr) => !expr.matches(event),
+            Expr::Condition(condition) => condition.matches(event),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, event: &CollectedEvent) -> bool {
+        let subject = match &self.key {
+            Key::Level => Some(Subject::Level(event.level)),
+            Key::Target => Some(Subject::Text(&event.target)),
+            Key::Time => Some(Subject::Time(event.time)),
+            Key::Span => Some(Subject::Any(
+                event.spans.iter().map(|span| span.name.as_str()).collect(),
+            )),
+            Key::Thread => event.thread().map(Subject::Text),
+            Key::Module => event.module_path.as_deref().map(Subject::Text),
+            Key::File => event.file.as_deref().map(Subject::Text),
+            Key::Source => event.source.as_deref().map(Subject::Text),
+            Key::Field(name) => event.fields.get(name).map(Subject::Field),
+        };
+
+        let Some(test) = &self.test else {
+            return match subject {
+                Some(Subject::Any(values)) => !values.is_empty(),
+                subject => subject.is_some(),
+            };
+        };
+        // negated tests also match events without the key.
+        let matched = subject.is_some_and(|subject| test.matches(&subject));
+        if test.is_negated() {
+            !matched
+        } else {
+            matched
+        }
+    }
+}
+
+impl Test {
+    fn is_negated(&self) -> bool {
+        match self {
+            Test::Compare(op, _) => *op == CmpOp::Ne,
+            Test::Glob { negated, .. } | Test::Regex { negated, .. } => *negated,
+        }
+    }
+
+    /// Whether the subject passes the test, ignoring negation.
+    fn matches(&self, subject: &Subject<'_>) -> bool {
+        if let Subject::Any(values) = subject {
+            return values.iter().any(|value| self.matches(&Subject::Text(value)));
+        }
+        match self {
+            Test::Compare(op, operand) => {
+                let op = if *op == CmpOp::Ne { CmpOp::Eq } else { *op };
+                compare(subject, operand).is_some_and(|ordering| op.holds(ordering))
+            }
+            Test::Glob { matcher, .. } => matcher.is_match(subject.text()),
+            Test::Regex { regex, .. } => regex.is_match(&subject.text()),
+        }
+    }
+}
+
+impl Subject<'_> {
+    fn text(&self) -> String {
+        match self {
+            Subject::Level(level) => level.as_str().to_owned(),
+            Subject::Time(time) => time.to_rfc3339(),
+            Subject::Text(text) => (*text).to_owned(),
+            Subject::Field(value) => value.to_string(),
+            Subject::Any(values) => values.join(":"),
+        }
+    }
+}
+
+impl CmpOp {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CmpOp::Eq => ordering.is_eq(),
+            CmpOp::Ne => ordering.is_ne(),
+            CmpOp::Lt => ordering.is_lt(),
+            CmpOp::Le => ordering.is_le(),
+            CmpOp::Gt => ordering.is_gt(),
+            CmpOp::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// Orders the subject relative to the operand, `None` if they are not
+/// comparable.
+fn compare(subject: &Subject<'_>, operand: &Operand) -> Option<Ordering> {
+    match (subject, operand) {
+        (Subject::Level(level), Operand::Level(other)) => {
+            Some(severity(*level).cmp(&severity(*other)))
+        }
+        (Subject::Time(time), Operand::DateTime(other)) => Some(time.cmp(other)),
+        (Subject::Time(time), Operand::TimeOfDay(other)) => {
+            Some(time.time().cmp(other))
+        }
+        (Subject::Field(value), Operand::Number(number, text)) => {
+            match value.as_f64() {
+                Some(value) => value.partial_cmp(number),
+                None => Some(value.to_string().as_str().cmp(text)),
+            }
+        }
+        (subject, Operand::Number(_, text) | Operand::Text(text)) => {
+            Some(subject.text().as_str().cmp(text))
+        }
+        _ => None,
+    }
+}
+
+/// Orders levels from `TRACE` to `ERROR`, i.e. the reverse of `Level`'s own
+/// ordering by verbosity.
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::TRACE => 0,
+        Level::DEBUG => 1,
+        Level::INFO => 2,
+        Level::WARN => 3,
+        Level::ERROR => 4,
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, pos: 0 };
+        parser.skip_whitespace();
+        if parser.at_end() {
+            return Err(parser.error("empty query"));
+        }
+        let expr = parser.parse_or()?;
+        parser.skip_whitespace();
+        if !parser.at_end() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(Self {
+            source: s.to_owned(),
+            expr,
+        })
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.reason, self.position)
+    }
+}
+
+impl Error for ParseQueryError {}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Expr, ParseQueryError> {
+        let mut expr = self.parse_and()?;
+        while self.eat_keyword("or") || self.eat_symbol("||") {
+            let right = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseQueryError> {
+        let mut expr = self.parse_not()?;
+        loop {
+            let explicit = self.eat_keyword("and") || self.eat_symbol("&&");
+            self.skip_whitespace();
+            let implicit = !explicit
+                && !self.at_end()
+                && !self.rest().starts_with(')')
+                && !self.rest().starts_with("||")
+                && !self.peek_keyword("or");
+            if !explicit && !implicit {
+                return Ok(expr);
+            }
+            let right = self.parse_not()?;
+            expr = Expr::And(Box::new(expr), Box::new(right));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseQueryError> {
+        self.skip_whitespace();
+        let bang = self.rest().starts_with('!')
+            && !self.rest()[1..].starts_with(['=', '~', ':']);
+        if self.eat_keyword("not") || (bang && self.eat_symbol("!")) {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseQueryError> {
+        self.skip_whitespace();
+        if self.eat_symbol("(") {
+            let expr = self.parse_or()?;
+            self.skip_whitespace();
+            if !self.eat_symbol(")") {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(expr);
+        }
+
+        let key_start = self.pos;
+        let key = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+        if key.is_empty() {
+            return Err(self.error("expected a field name"));
+        }
+        let key = match key {
+            "level" => Key::Level,
+            "target" => Key::Target,
+            "time" => Key::Time,
+            "span" => Key::Span,
+            "thread" => Key::Thread,
+            "module" => Key::Module,
+            "file" => Key::File,
+            "source" => Key::Source,
+            field => Key::Field(field.to_owned()),
+        };
+
+        self.skip_whitespace();
+        let Some(op) = self.parse_operator() else {
+            return Ok(Expr::Condition(Condition { key, test: None }));
+        };
+        self.skip_whitespace();
+        let value_start = self.pos;
+        let value = self.parse_value()?;
+        let invalid = |reason: String| ParseQueryError {
+            position: value_start,
+            reason,
+        };
+
+        let test = match op {
+            "~" | "!~" => Test::Regex {
+                negated: op == "!~",
+                regex: Regex::new(&value).map_err(|err| {
+                    // the last line of the error describes the problem.
+                    let message = err.to_string();
+                    let reason = message.lines().last().unwrap_or_default();
+                    invalid(format!(
+                        "invalid regex: {}",
+                        reason.trim_start_matches("error: ")
+                    ))
+                })?,
+            },
+            ":" | "!:" => Test::Glob {
+                negated: op == "!:",
+                matcher: Glob::new(&value)
+                    .map_err(|err| invalid(format!("invalid glob: {}", err)))?
+                    .compile_matcher(),
+            },
+            op => {
+                let op = match op {
+                    "=" | "==" => CmpOp::Eq,
+                    "!=" => CmpOp::Ne,
+                    "<" => CmpOp::Lt,
+                    "<=" => CmpOp::Le,
+                    ">" => CmpOp::Gt,
+                    _ => CmpOp::Ge,
+                };
+                let operand = match key {
+                    Key::Level => Operand::Level(
+                        value
+                            .parse()
+                            .map_err(|_| invalid("expected a level".to_owned()))?,
+                    ),
+                    Key::Time => parse_time(&value)
+                        .ok_or_else(|| invalid("expected a time".to_owned()))?,
+                    _ => match value.parse() {
+                        Ok(number) => Operand::Number(number, value),
+                        Err(_) => Operand::Text(value),
+                    },
+                };
+                if matches!(key, Key::Span) && !matches!(op, CmpOp::Eq | CmpOp::Ne)
+                {
+                    return Err(ParseQueryError {
+                        position: key_start,
+                        reason: "spans can only be compared with `=` or `!=`"
+                            .to_owned(),
+                    });
+                }
+                Test::Compare(op, operand)
+            }
+        };
+        Ok(Expr::Condition(Condition {
+            key,
+            test: Some(test),
+        }))
+    }
+
+    fn parse_operator(&mut self) -> Option<&'static str> {
+        const OPERATORS: [&str; 11] =
+            [">=", "<=", "!=", "!~", "!:", "==", "=", "<", ">", ":", "~"];
+        let op = OPERATORS.into_iter().find(|op| self.rest().starts_with(op))?;
+        self.pos += op.len();
+        Some(op)
+    }
+
+    fn parse_value(&mut self) -> Result<String, ParseQueryError> {
+        if !self.eat_symbol("\"") {
+            let value = self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')');
+            if value.is_empty() {
+                return Err(self.error("expected a value"));
+            }
+            return Ok(value.to_owned());
+        }
+
+        let start = self.pos - 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(ParseQueryError {
+            position: start,
+            reason: "unterminated string".to_owned(),
+        })
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c| !f(c))
+            .unwrap_or(self.input.len() - start);
+        self.pos += len;
+        &self.input[start..self.pos]
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        let rest = self.rest();
+        rest.get(..keyword.len())
+            .is_some_and(|word| word.eq_ignore_ascii_case(keyword))
+            && rest[keyword.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || c == '(')
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += keyword.len();
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(symbol);
+        if found {
+            self.pos += symbol.len();
+        }
+        found
+    }
+
+    fn error(&self, reason: &str) -> ParseQueryError {
+        ParseQueryError {
+            position: self.pos,
+            reason: reason.to_owned(),
+        }
+    }
+}
+
+fn parse_time(s: &str) -> Option<Operand> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(Operand::DateTime(time.with_timezone(&Local)));
+    }
+    let local = |naive: NaiveDateTime| {
+        Local.from_local_datetime(&naive).earliest().map(Operand::DateTime)
+    };
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return local(naive);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return local(date.and_time(NaiveTime::MIN));
+    }
+    ["%H:%M:%S%.f", "%H:%M"]
+        .into_iter()
+        .find_map(|format| NaiveTime::parse_from_str(s, format).ok())
+        .map(Operand::TimeOfDay)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn event(level: Level, target: &str, fields: &[(&str, FieldValue)]) -> CollectedEvent {
+        CollectedEvent {
+            target: target.to_owned(),
+            level,
+            fields: fields
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), value.clone()))
+                .collect::<BTreeMap<_, _>>(),
+            time: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            spans: Vec::new(),
+            name: "event".to_owned(),
+            module_path: None,
+            file: None,
+            line: None,
+            thread_id: None,
+            thread_name: None,
+            source: None,
+        }
+    }
+
+    fn message(text: &str) -> (&'static str, FieldValue) {
+        ("message", FieldValue::Str(text.to_owned()))
+    }
+
+    fn matches(query: &str, event: &CollectedEvent) -> bool {
+        query.parse::<Query>().unwrap().matches(event)
+    }
+
+    #[test]
+    fn matches_example_query() {
+        let query = r#"level>=warn and target:my_app::* and user_id=42 and message~"timeout""#;
+        let user = ("user_id", FieldValue::U64(42));
+        let matching = event(Level::ERROR, "my_app::net", &[user.clone(), message("a timeout")]);
+        assert!(matches(query, &matching));
+
+        let info = event(Level::INFO, "my_app::net", &[user.clone(), message("a timeout")]);
+        let other_target = event(Level::WARN, "hyper", &[user.clone(), message("a timeout")]);
+        let other_user = event(
+            Level::WARN,
+            "my_app",
+            &[("user_id", FieldValue::U64(7)), message("a timeout")],
+        );
+        let no_timeout = event(Level::WARN, "my_app::db", &[user, message("done")]);
+        for event in [info, other_target, other_user, no_timeout] {
+            assert!(!matches(query, &event), "{:?}", event);
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let warn = event(Level::WARN, "a", &[]);
+        let info = event(Level::INFO, "b", &[]);
+        assert!(matches("level=warn or level=info and target=a", &warn));
+        assert!(!matches("level=warn or level=info and target=a", &info));
+        assert!(!matches("(level=warn or level=info) and target=a", &info));
+        // adjacent conditions are combined with `and`.
+        assert!(matches("level=warn target=a || target=b", &info));
+        assert!(!matches("level=warn target=a", &info));
+    }
+
+    #[test]
+    fn negates() {
+        let warn = event(Level::WARN, "my_app", &[message("timeout")]);
+        assert!(!matches("not level=warn", &warn));
+        assert!(matches("!target:hyper*", &warn));
+        assert!(matches("not not level=warn", &warn));
+        assert!(matches("NOT (level=info or target=x)", &warn));
+        assert!(!matches("message!~time", &warn));
+        assert!(matches("target!=hyper", &warn));
+        // negated tests match events without the field.
+        assert!(matches("user!=bob", &warn));
+        assert!(!matches("user=bob", &warn));
+        assert!(matches("message", &warn) && !matches("!message", &warn));
+    }
+
+    #[test]
+    fn compares_values() {
+        let event = event(
+            Level::INFO,
+            "my_app",
+            &[("latency", FieldValue::F64(1.5)), ("user", FieldValue::Str("bob".to_owned()))],
+        );
+        assert!(matches("latency>1 latency<=1.5 latency!=2", &event));
+        assert!(matches("level<warn level>=info level==INFO", &event));
+        assert!(matches(r#"user="bob" user>alice"#, &event));
+        assert!(matches("time>=2024-05-01 time<\"2024-05-01 12:00:01\"", &event));
+        assert!(matches("time=12:00 time>11:59:59", &event));
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let error = |query: &str| query.parse::<Query>().unwrap_err();
+        assert_eq!(error("").reason, "empty query");
+        assert_eq!(error("level>=loud").position, 7);
+        assert_eq!(error("level>=loud").reason, "expected a level");
+        assert_eq!(error("level=warn and").position, 14);
+        assert_eq!(error("(level=warn").reason, "expected `)`");
+        assert_eq!(error("level=warn)").position, 10);
+        assert_eq!(error(r#"message="abc"#).position, 8);
+        assert_eq!(error(r#"message="abc"#).reason, "unterminated string");
+        assert_eq!(error("message~(").position, 8);
+        assert_eq!(error("message~\"(\"").position, 8);
+        assert!(error("message~\"(\"").reason.starts_with("invalid regex"));
+        assert_eq!(error("span>a").position, 0);
+        assert_eq!(error("level=").reason, "expected a value");
+        assert_eq!(error("level=warn and = x").position, 15);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use tracing::span::{Attributes, Record};
+use tracing::Level;
+
+use super::field::{FieldValue, FieldVisitor};
+
+/// A span enclosing a collected event, as it was when the event was emitted.
+#[derive(Debug, Clone)]
+pub struct SpanInfo {
+    /// Identifies the span within its collector. Unlike `tracing::span::Id`
+    /// it is never reused for a later span.
+    pub id: u64,
+    pub name: String,
+    pub target: String,
+    pub level: Level,
+    pub fields: BTreeMap<String, FieldValue>,
+}
+
+impl SpanInfo {
+    pub(crate) fn new(id: u64, attrs: &Attributes<'_>) -> Self {
+        let meta = attrs.metadata();
+        let mut fields = BTreeMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+
+        Self {
+            id,
+            name: meta.name().to_owned(),
+            target: meta.target().to_owned(),
+            level: meta.level().to_owned(),
+            fields,
+        }
+    }
+
+    pub(crate) fn record(&mut self, values: &Record<'_>) {
+        values.record(&mut FieldVisitor(&mut self.fields));
+    }
+}
+
+/// Formats a span chain like `tracing_subscriber::fmt` does, e.g.
+/// `outer{id=1}:inner`.
+pub fn format_span_path(spans: &[Arc<SpanInfo>], with_fields: bool) -> String {
+    let mut path = String::new();
+    for span in spans {
+        if !path.is_empty() {
+            path.push(':');
+        }
+        path.push_str(&span.name);
+        if with_fields && !span.fields.is_empty() {
+            let fields = span
+                .fields
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            path.push_str(&format!("{{{}}}", fields));
+        }
+    }
+    path
+}
+
+/// Lifecycle of a span: when it was created, entered, exited and closed.
+#[derive(Debug, Clone)]
+pub struct SpanRecord {
+    pub info: Arc<SpanInfo>,
+    /// [`SpanInfo::id`] of the parent span, if any.
+    pub parent: Option<u64>,
+    /// Name (or id, for unnamed threads) of the thread that created the span.
+    pub thread: String,
+    pub created: DateTime<Local>,
+    pub closed: Option<DateTime<Local>>,
+    pub entries: Vec<SpanEntry>,
+}
+
+/// A single enter/exit pair of a span.
+#[derive(Debug, Clone)]
+pub struct SpanEntry {
+    pub thread: String,
+    pub entered: DateTime<Local>,
+    pub exited: Option<DateTime<Local>>,
+}
+
+impl SpanRecord {
+    /// End of the span's lifetime, `now` if it has not been closed yet.
+    pub fn end(&self, now: DateTime<Local>) -> DateTime<Local> {
+        self.closed.unwrap_or(now)
+    }
+
+    /// Total time spent inside the span.
+    pub fn busy(&self, now: DateTime<Local>) -> Duration {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let exited = entry.exited.unwrap_or(now);
+                (exited - entry.entered).to_std().unwrap_or_default()
+            })
+            .sum()
+    }
+
+    /// Time the span was alive without being entered.
+    pub fn idle(&self, now: DateTime<Local>) -> Duration {
+        let alive = (self.end(now) - self.created).to_std().unwrap_or_default();
+        alive.saturating_sub(self.busy(now))
+    }
+
+    /// Thread the span is attributed to in timelines: the thread it was first
+    /// entered on, or the creating thread if it was never entered.
+    pub fn lane(&self) -> &str {
+        self.entries
+            .first()
+            .map_or(&self.thread, |entry| &entry.thread)
+    }
+}
+
+/// Span records ordered by [`SpanInfo::id`], i.e. by creation.
+#[derive(Debug, Default)]
+pub(crate) struct SpanStore {
+    spans: BTreeMap<u64, SpanRecord>,
+    /// Ids of the closed spans, in the order they were closed.
+    closed: VecDeque<u64>,
+    capacity: Option<usize>,
+}
+
+impl SpanStore {
+    pub fn spans(&self) -> impl Iterator<Item = &SpanRecord> {
+        self.spans.values()
+    }
+
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn insert(&mut self, record: SpanRecord) {
+        self.spans.insert(record.info.id, record);
+        self.evict();
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut SpanRecord> {
+        self.spans.get_mut(&id)
+    }
+
+    /// Marks the span as closed at `now`, exiting it on every thread.
+    pub fn close(&mut self, id: u64, now: DateTime<Local>) {
+        let Some(record) = self.spans.get_mut(&id) else { return };
+        if record.closed.is_some() {
+            return;
+        }
+        for entry in &mut record.entries {
+            entry.exited.get_or_insert(now);
+        }
+        record.closed = Some(now);
+        self.closed.push_back(id);
+        self.evict();
+    }
+
+    /// Forgets all closed spans.
+    pub fn clear(&mut self) {
+        for id in self.closed.drain(..) {
+            self.spans.remove(&id);
+        }
+    }
+
+    /// Drops the earliest closed spans while over capacity.
+    fn evict(&mut self) {
+        let Some(capacity) = self.capacity else { return };
+        while self.spans.len() > capacity {
+            let Some(id) = self.closed.pop_front() else { break };
+            self.spans.remove(&id);
+        }
+    }
+}
+
+/// Span extension holding the current state of a span, shared by all events
+/// emitted inside it.
+pub(crate) struct SpanData(pub Arc<SpanInfo>);
+
+/// Name of the current thread, or its id if it is unnamed.
+pub(crate) fn current_thread_name() -> String {
+    let thread = std::thread::current();
+    thread
+        .name()
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{:?}", thread.id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn record(id: u64) -> SpanRecord {
+        SpanRecord {
+            info: Arc::new(SpanInfo {
+                id,
+                name: "span".to_owned(),
+                target: "app".to_owned(),
+                level: Level::INFO,
+                fields: BTreeMap::new(),
+            }),
+            parent: None,
+            thread: "main".to_owned(),
+            created: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            closed: None,
+            entries: Vec::new(),
+        }
+    }
+
+    fn ids(store: &SpanStore) -> Vec<u64> {
+        store.spans().map(|record| record.info.id).collect()
+    }
+
+    #[test]
+    fn evicts_first_closed_spans() {
+        let mut store = SpanStore::default();
+        store.set_capacity(Some(3));
+        let now = Local::now();
+        for id in 1..=3 {
+            store.insert(record(id));
+        }
+        store.close(3, now);
+        store.close(2, now);
+        store.close(2, now);
+
+        // open spans are kept even over capacity.
+        store.insert(record(4));
+        assert_eq!(ids(&store), [1, 2, 4]);
+        store.insert(record(5));
+        assert_eq!(ids(&store), [1, 4, 5]);
+        store.insert(record(6));
+        assert_eq!(ids(&store), [1, 4, 5, 6]);
+
+        store.close(5, now);
+        assert_eq!(ids(&store), [1, 4, 6]);
+        store.close(1, now);
+        store.clear();
+        assert_eq!(ids(&store), [4, 6]);
+    }
+}
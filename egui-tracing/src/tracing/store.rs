@@ -0,0 +1,498 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
+use std::sync::Arc;
+
+use tracing::Level;
+
+use super::collector::{EventCursor, EventsSince, EvictionPolicy, LevelCounts};
+use super::event::CollectedEvent;
+
+/// Retained events together with their sequence numbers, kept per level so
+/// that every eviction policy can remove its victim in constant time.
+/// Evicted sequence numbers are kept in a log, so that readers can remove
+/// them from their own copy; readers that fall behind the log, or read across
+/// a clear, start over with a new generation.
+#[derive(Debug)]
+pub struct EventStore {
+    /// Retained events per level, most verbose level first, each in
+    /// collection order.
+    levels: [VecDeque<(u64, Arc<CollectedEvent>)>; 5],
+    len: usize,
+    generation: u64,
+    next_seq: u64,
+    /// Sequence numbers of the latest evictions, at most `capacity` of them.
+    evictions: VecDeque<u64>,
+    /// Number of evictions logged before the front of `evictions`.
+    evictions_start: u64,
+    capacity: Option<usize>,
+    policy: EvictionPolicy,
+    target_counts: BTreeMap<String, LevelCounts>,
+    evicted: u64,
+}
+
+impl Default for EventStore {
+    fn default() -> Self {
+        Self {
+            levels: Default::default(),
+            len: 0,
+            // generation 0 is reserved for `EventCursor::default()`
+            generation: 1,
+            next_seq: 0,
+            evictions: VecDeque::new(),
+            evictions_start: 0,
+            capacity: None,
+            policy: EvictionPolicy::default(),
+            target_counts: BTreeMap::new(),
+            evicted: 0,
+        }
+    }
+}
+
+impl EventStore {
+    /// The retained events in collection order.
+    pub fn events(&self) -> Vec<Arc<CollectedEvent>> {
+        self.retained_since(0)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    pub fn target_counts(&self) -> &BTreeMap<String, LevelCounts> {
+        &self.target_counts
+    }
+
+    /// Sequence number of the next retained event.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Number of retained events with a sequence number in one of `ranges`.
+    pub fn count_retained(&self, ranges: &[Range<u64>]) -> usize {
+        let count = |events: &VecDeque<(u64, Arc<CollectedEvent>)>, range: &Range<u64>| {
+            events.partition_point(|(seq, _)| *seq < range.end)
+                - events.partition_point(|(seq, _)| *seq < range.start)
+        };
+        self.levels
+            .iter()
+            .flat_map(|events| ranges.iter().map(move |range| count(events, range)))
+            .sum()
+    }
+
+    pub fn since(&self, cursor: EventCursor) -> EventsSince {
+        let reset = self.is_stale(cursor);
+        let (next_seq, evicted) = if reset {
+            (0, Vec::new())
+        } else {
+            let mut evicted: Vec<_> = self
+                .evictions
+                .iter()
+                .skip((cursor.evictions - self.evictions_start) as usize)
+                .copied()
+                .filter(|seq| *seq < cursor.next_seq)
+                .collect();
+            evicted.sort_unstable();
+            (cursor.next_seq, evicted)
+        };
+        let (seqs, events) = self.retained_since(next_seq).into_iter().unzip();
+
+        EventsSince {
+            reset,
+            evicted,
+            events,
+            seqs,
+            cursor: EventCursor {
+                generation: self.generation,
+                next_seq: self.next_seq,
+                evictions: self.evictions_start + self.evictions.len() as u64,
+            },
+        }
+    }
+
+    pub fn count_since(&self, cursor: EventCursor) -> usize {
+        if self.is_stale(cursor) {
+            return self.len;
+        }
+        self.levels
+            .iter()
+            .map(|events| {
+                events.len() - events.partition_point(|(seq, _)| *seq < cursor.next_seq)
+            })
+            .sum()
+    }
+
+    /// Whether a reader at `cursor` has to start over, after a clear or when
+    /// it missed evictions that are no longer logged.
+    fn is_stale(&self, cursor: EventCursor) -> bool {
+        cursor.generation != self.generation || cursor.evictions < self.evictions_start
+    }
+
+    /// Retained events from sequence number `seq` on, merged in collection
+    /// order.
+    fn retained_since(&self, seq: u64) -> Vec<(u64, Arc<CollectedEvent>)> {
+        let mut retained: Vec<_> = self
+            .levels
+            .iter()
+            .flat_map(|events| {
+                let start = events.partition_point(|(s, _)| *s < seq);
+                events.range(start..).cloned()
+            })
+            .collect();
+        // concatenated sorted runs, which the stable sort merges.
+        retained.sort_by_key(|(seq, _)| *seq);
+        retained
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        if let Some(capacity) = capacity {
+            while self.len > capacity {
+                self.evict_one(None);
+            }
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn push(&mut self, event: CollectedEvent) {
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                self.evicted += 1;
+                return;
+            }
+            if self.len >= capacity && !self.evict_one(Some(event.level)) {
+                self.evicted += 1;
+                return;
+            }
+        }
+        match self.target_counts.get_mut(&event.target) {
+            Some(counts) => counts.increment(event.level),
+            None => {
+                let mut counts = LevelCounts::default();
+                counts.increment(event.level);
+                self.target_counts.insert(event.target.clone(), counts);
+            }
+        }
+        self.levels[LevelCounts::index(event.level)].push_back((self.next_seq, Arc::new(event)));
+        self.len += 1;
+        self.next_seq += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.generation += 1;
+        self.levels = Default::default();
+        self.len = 0;
+        self.evictions_start += self.evictions.len() as u64;
+        self.evictions.clear();
+        self.target_counts.clear();
+    }
+
+    /// Removes one retained event according to the eviction policy, making
+    /// room for an incoming event of `incoming` level. Returns `false` when
+    /// the incoming event should be dropped instead.
+    fn evict_one(&mut self, incoming: Option<Level>) -> bool {
+        let removed = match self.policy {
+            EvictionPolicy::DropOldest => self
+                .levels
+                .iter_mut()
+                .filter(|events| !events.is_empty())
+                .min_by_key(|events| events.front().map(|(seq, _)| *seq))
+                .and_then(VecDeque::pop_front),
+            EvictionPolicy::DropNewest => {
+                if incoming.is_some() {
+                    return false;
+                }
+                self.levels
+                    .iter_mut()
+                    .max_by_key(|events| events.back().map(|(seq, _)| *seq))
+                    .and_then(VecDeque::pop_back)
+            }
+            EvictionPolicy::DropLowestLevel => {
+                let Some(lowest) = self.levels.iter().position(|events| !events.is_empty())
+                else {
+                    return false;
+                };
+                if incoming.is_some_and(|level| LevelCounts::index(level) < lowest) {
+                    return false;
+                }
+                self.levels[lowest].pop_front()
+            }
+        };
+
+        let Some((seq, event)) = removed else {
+            return false;
+        };
+        if let Some(counts) = self.target_counts.get_mut(&event.target) {
+            counts.decrement(event.level);
+            if counts.total() == 0 {
+                self.target_counts.remove(&event.target);
+            }
+        }
+        self.evictions.push_back(seq);
+        if self.evictions.len() > self.capacity.unwrap_or(0) {
+            self.evictions.pop_front();
+            self.evictions_start += 1;
+        }
+        self.len -= 1;
+        self.evicted += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+
+    fn event(level: Level) -> CollectedEvent {
+        CollectedEvent {
+            target: "my_crate".to_owned(),
+            level,
+            fields: BTreeMap::new(),
+            time: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            spans: Vec::new(),
+            name: "event".to_owned(),
+            module_path: None,
+            file: None,
+            line: None,
+            thread_id: None,
+            thread_name: None,
+            source: None,
+        }
+    }
+
+    fn filled(capacity: usize, policy: EvictionPolicy, levels: &[Level]) -> EventStore {
+        let mut store = EventStore::default();
+        store.set_capacity(Some(capacity));
+        store.set_policy(policy);
+        for level in levels {
+            store.push(event(*level));
+        }
+        store
+    }
+
+    /// Sequence numbers and levels of the retained events.
+    fn retained(store: &EventStore) -> Vec<(u64, Level)> {
+        let since = store.since(EventCursor::default());
+        since
+            .seqs
+            .into_iter()
+            .zip(since.events.iter().map(|event| event.level))
+            .collect()
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let store = filled(
+            3,
+            EvictionPolicy::DropOldest,
+            &[Level::ERROR, Level::TRACE, Level::INFO, Level::DEBUG],
+        );
+        assert_eq!(
+            retained(&store),
+            [(1, Level::TRACE), (2, Level::INFO), (3, Level::DEBUG)]
+        );
+        assert_eq!(store.evicted(), 1);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let store = filled(
+            2,
+            EvictionPolicy::DropNewest,
+            &[Level::TRACE, Level::DEBUG, Level::INFO, Level::ERROR],
+        );
+        // even more severe incoming events are dropped.
+        assert_eq!(retained(&store), [(0, Level::TRACE), (1, Level::DEBUG)]);
+        assert_eq!(store.evicted(), 2);
+    }
+
+    #[test]
+    fn drop_lowest_level() {
+        let mut store = filled(
+            3,
+            EvictionPolicy::DropLowestLevel,
+            &[Level::INFO, Level::TRACE, Level::DEBUG, Level::WARN],
+        );
+        assert_eq!(
+            retained(&store),
+            [(0, Level::INFO), (2, Level::DEBUG), (3, Level::WARN)]
+        );
+
+        // more verbose than everything retained, dropped.
+        store.push(event(Level::TRACE));
+        assert_eq!(
+            retained(&store),
+            [(0, Level::INFO), (2, Level::DEBUG), (3, Level::WARN)]
+        );
+
+        store.push(event(Level::INFO));
+        assert_eq!(
+            retained(&store),
+            [(0, Level::INFO), (3, Level::WARN), (4, Level::INFO)]
+        );
+        // the oldest event of the lowest level makes room for an equal one.
+        store.push(event(Level::INFO));
+        assert_eq!(
+            retained(&store),
+            [(3, Level::WARN), (4, Level::INFO), (5, Level::INFO)]
+        );
+        assert_eq!(store.evicted(), 4);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        for policy in [
+            EvictionPolicy::DropOldest,
+            EvictionPolicy::DropNewest,
+            EvictionPolicy::DropLowestLevel,
+        ] {
+            let store = filled(0, policy, &[Level::INFO, Level::ERROR]);
+            assert!(retained(&store).is_empty());
+            assert_eq!(store.evicted(), 2);
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let levels = [Level::WARN, Level::TRACE, Level::ERROR, Level::DEBUG, Level::INFO];
+
+        let mut store = filled(5, EvictionPolicy::DropOldest, &levels);
+        store.set_capacity(Some(2));
+        assert_eq!(retained(&store), [(3, Level::DEBUG), (4, Level::INFO)]);
+        assert_eq!(store.evicted(), 3);
+
+        let mut store = filled(5, EvictionPolicy::DropNewest, &levels);
+        store.set_capacity(Some(2));
+        assert_eq!(retained(&store), [(0, Level::WARN), (1, Level::TRACE)]);
+
+        let mut store = filled(5, EvictionPolicy::DropLowestLevel, &levels);
+        store.set_capacity(Some(2));
+        assert_eq!(retained(&store), [(0, Level::WARN), (2, Level::ERROR)]);
+
+        // growing again evicts nothing.
+        store.set_capacity(None);
+        store.push(event(Level::TRACE));
+        assert_eq!(retained(&store).len(), 3);
+        assert_eq!(store.evicted(), 3);
+    }
+
+    #[test]
+    fn since_appended() {
+        let mut store = filled(10, EvictionPolicy::DropOldest, &[Level::INFO; 2]);
+        // a new reader starts with a reset.
+        let since = store.since(EventCursor::default());
+        assert!(since.reset);
+        assert_eq!(since.seqs, [0, 1]);
+        let cursor = since.cursor;
+        assert_eq!(store.count_since(cursor), 0);
+
+        store.push(event(Level::DEBUG));
+        store.push(event(Level::WARN));
+        assert_eq!(store.count_since(cursor), 2);
+        let since = store.since(cursor);
+        assert!(!since.reset);
+        assert!(since.evicted.is_empty());
+        assert_eq!(since.seqs, [2, 3]);
+        assert_eq!(since.events[1].level, Level::WARN);
+        assert!(store.since(since.cursor).seqs.is_empty());
+    }
+
+    #[test]
+    fn since_evicted() {
+        let mut store = filled(4, EvictionPolicy::DropLowestLevel, &[Level::INFO; 3]);
+        let cursor = store.since(EventCursor::default()).cursor;
+
+        store.push(event(Level::TRACE));
+        // evicts the trace event, which the reader has not seen.
+        store.push(event(Level::INFO));
+        // evicts the oldest info event, which it has.
+        store.push(event(Level::INFO));
+        assert_eq!(store.count_since(cursor), 2);
+        let since = store.since(cursor);
+        assert!(!since.reset);
+        assert_eq!(since.evicted, [0]);
+        assert_eq!(since.seqs, [4, 5]);
+
+        store.push(event(Level::ERROR));
+        let since = store.since(since.cursor);
+        assert_eq!(since.evicted, [1]);
+        assert_eq!(since.seqs, [6]);
+    }
+
+    #[test]
+    fn since_behind_eviction_log() {
+        let mut store = filled(2, EvictionPolicy::DropOldest, &[Level::INFO; 2]);
+        let cursor = store.since(EventCursor::default()).cursor;
+        store.push(event(Level::INFO));
+        store.push(event(Level::INFO));
+        let caught_up = store.since(cursor);
+        assert!(!caught_up.reset);
+        assert_eq!(caught_up.evicted, [0, 1]);
+
+        // more evictions than the log keeps.
+        store.push(event(Level::INFO));
+        assert_eq!(store.count_since(cursor), 2);
+        let since = store.since(cursor);
+        assert!(since.reset);
+        assert!(since.evicted.is_empty());
+        assert_eq!(since.seqs, [3, 4]);
+        assert!(!store.since(since.cursor).reset);
+        assert!(!store.since(caught_up.cursor).reset);
+    }
+
+    #[test]
+    fn since_clear() {
+        let mut store = filled(10, EvictionPolicy::DropOldest, &[Level::INFO; 3]);
+        let cursor = store.since(EventCursor::default()).cursor;
+        store.clear();
+        assert!(store.since(cursor).reset);
+        assert_eq!(store.count_since(cursor), 0);
+
+        store.push(event(Level::INFO));
+        assert_eq!(store.count_since(cursor), 1);
+        let since = store.since(cursor);
+        assert!(since.reset);
+        // sequence numbers are not reused.
+        assert_eq!(since.seqs, [3]);
+        assert!(!store.since(since.cursor).reset);
+    }
+
+    #[test]
+    fn counts_retained_events_per_target() {
+        let targeted = |target: &str, level| CollectedEvent {
+            target: target.to_owned(),
+            ..event(level)
+        };
+        let mut store = filled(2, EvictionPolicy::DropNewest, &[]);
+        store.push(targeted("a", Level::INFO));
+        store.push(targeted("b", Level::WARN));
+        store.push(targeted("c", Level::ERROR));
+        let targets: Vec<_> = store.target_counts().keys().collect();
+        assert_eq!(targets, ["a", "b"]);
+        assert_eq!(store.target_counts()["b"].get(Level::WARN), 1);
+
+        store.set_policy(EvictionPolicy::DropOldest);
+        store.push(targeted("b", Level::INFO));
+        let targets: Vec<_> = store.target_counts().keys().collect();
+        assert_eq!(targets, ["b"]);
+        assert_eq!(store.target_counts()["b"].total(), 2);
+
+        store.clear();
+        assert!(store.target_counts().is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::sync::Arc;
+
+use globset::{GlobSet, GlobSetBuilder};
+
+use super::search::Search;
+use super::short_message;
+use super::state::{
+    is_within,
+    Column,
+    LevelFilter,
+    LogsState,
+    SearchState,
+    Sort,
+    SourceFilter,
+    TargetEntry,
+    TargetMode,
+};
+use crate::tracing::{
+    CollectedEvent,
+    EventCollector,
+    EventCursor,
+    EventsSince,
+    FieldValue,
+    Query,
+};
+
+/// Per-widget copy of the collected events and of the subset passing the
+/// current filters in the current order, updated incrementally from the
+/// collector every frame.
+pub struct LogsCache {
+    cursor: EventCursor,
+    events: VecDeque<Arc<CollectedEvent>>,
+    /// Sequence numbers of `events`.
+    seqs: VecDeque<u64>,
+    filtered: Vec<Arc<CollectedEvent>>,
+    /// Whether `filtered` is in collection order, so that evicted events are
+    /// at its front. Events of other sources or read from files often arrive
+    /// out of time order.
+    in_collection_order: bool,
+    filter: Option<EventFilter>,
+    sort: Option<Sort>,
+    /// Keys of the structured fields seen in events, except the message.
+    field_keys: BTreeSet<String>,
+    sources: BTreeSet<Option<String>>,
+}
+
+impl Default for LogsCache {
+    fn default() -> Self {
+        Self {
+            cursor: EventCursor::default(),
+            events: VecDeque::new(),
+            seqs: VecDeque::new(),
+            filtered: Vec::new(),
+            in_collection_order: true,
+            filter: None,
+            sort: None,
+            field_keys: BTreeSet::new(),
+            sources: BTreeSet::new(),
+        }
+    }
+}
+
+impl LogsCache {
+    pub fn update(
+        &mut self,
+        collector: &EventCollector,
+        state: &LogsState,
+        span_filter: Option<u64>,
+    ) {
+        // a paused view keeps its events, but still applies filter changes.
+        let since = if state.paused {
+            EventsSince {
+                reset: false,
+                evicted: Vec::new(),
+                events: Vec::new(),
+                seqs: Vec::new(),
+                cursor: self.cursor,
+            }
+        } else {
+            collector.events_since(self.cursor)
+        };
+        self.cursor = since.cursor;
+
+        let refilter = since.reset
+            || self.sort != state.sort
+            || !self
+                .filter
+                .as_ref()
+                .is_some_and(|filter| filter.is_current(state, span_filter));
+
+        if since.reset {
+            self.events.clear();
+            self.seqs.clear();
+            self.field_keys.clear();
+            self.sources.clear();
+        }
+        for event in &since.events {
+            if !self.sources.contains(&event.source) {
+                self.sources.insert(event.source.clone());
+            }
+            for key in event.fields.keys() {
+                if key != "message"
+                    && !key.starts_with("log.")
+                    && !self.field_keys.contains(key)
+                {
+                    self.field_keys.insert(key.clone());
+                }
+            }
+        }
+        let (evicted, oldest) = self.remove_events(&since.evicted);
+
+        if refilter {
+            let filter = EventFilter::new(state, span_filter);
+            self.events.extend(since.events);
+            self.seqs.extend(since.seqs);
+            self.filtered = self
+                .events
+                .iter()
+                .filter(|event| filter.matches(event))
+                .cloned()
+                .collect();
+            let sort = state.sort.as_ref();
+            self.in_collection_order =
+                sort.is_none() && is_ordered(&self.filtered, None);
+            sort_events(&mut self.filtered, sort);
+            self.filter = Some(filter);
+            self.sort = state.sort.clone();
+        } else {
+            self.remove_evicted(&evicted, oldest);
+            let filter = self.filter.as_ref().unwrap();
+            let sort = self.sort.as_ref();
+            let mut added: Vec<_> = since
+                .events
+                .iter()
+                .filter(|event| filter.matches(event))
+                .cloned()
+                .collect();
+            let appended = is_ordered(&added, sort)
+                && self.filtered.last().zip(added.first()).is_none_or(|(last, first)| {
+                    compare(last, first, sort) != Ordering::Greater
+                });
+            if appended {
+                self.filtered.extend(added);
+            } else {
+                // e.g. events of a file or another source, merged in time order.
+                self.in_collection_order = false;
+                sort_events(&mut added, sort);
+                let filtered = std::mem::take(&mut self.filtered);
+                self.filtered = merge(filtered, added, |a, b| compare(a, b, sort));
+            }
+            self.events.extend(since.events);
+            self.seqs.extend(since.seqs);
+        }
+    }
+
+    /// Removes the events with the given sequence numbers from the copy.
+    /// Returns them, and whether they were the oldest events.
+    fn remove_events(&mut self, seqs: &[u64]) -> (Vec<Arc<CollectedEvent>>, bool) {
+        let evicted: HashSet<_> = seqs.iter().copied().collect();
+        // usually the oldest events are evicted.
+        let front = self
+            .seqs
+            .iter()
+            .take_while(|seq| evicted.contains(seq))
+            .count();
+        self.seqs.drain(..front);
+        let mut removed: Vec<_> = self.events.drain(..front).collect();
+        if front == evicted.len() {
+            return (removed, true);
+        }
+
+        let mut seqs = self.seqs.iter();
+        self.events.retain(|event| {
+            let keep = seqs.next().is_none_or(|seq| !evicted.contains(seq));
+            if !keep {
+                removed.push(event.clone());
+            }
+            keep
+        });
+        self.seqs.retain(|seq| !evicted.contains(seq));
+        (removed, false)
+    }
+
+    fn remove_evicted(&mut self, evicted: &[Arc<CollectedEvent>], oldest: bool) {
+        // evicted events are at the front of `filtered` only if they were the
+        // oldest ones and nothing was reordered.
+        if !self.in_collection_order || !oldest {
+            let evicted: HashSet<_> = evicted.iter().map(Arc::as_ptr).collect();
+            self.filtered
+                .retain(|event| !evicted.contains(&Arc::as_ptr(event)));
+            return;
+        }
+
+        let mut evicted_filtered = 0;
+        for event in evicted {
+            if self
+                .filtered
+                .get(evicted_filtered)
+                .is_some_and(|filtered| Arc::ptr_eq(filtered, event))
+            {
+                evicted_filtered += 1;
+            }
+        }
+        self.filtered.drain(..evicted_filtered);
+    }
+
+    /// Number of events collected since the last update, e.g. while paused.
+    pub fn new_events(&self, collector: &EventCollector) -> usize {
+        collector.count_since(self.cursor)
+    }
+
+    pub fn filtered(&self) -> &[Arc<CollectedEvent>] {
+        &self.filtered
+    }
+
+    pub fn field_keys(&self) -> &BTreeSet<String> {
+        &self.field_keys
+    }
+
+    /// Sources of the events seen, `None` for events of this process.
+    pub fn sources(&self) -> &BTreeSet<Option<String>> {
+        &self.sources
+    }
+
+    /// The search the filtered events were matched against.
+    pub fn search(&self) -> Option<&Search> {
+        self.filter.as_ref()?.search.as_ref()
+    }
+
+    pub fn search_error(&self) -> Option<String> {
+        self.filter.as_ref()?.search_error.clone()
+    }
+}
+
+struct EventFilter {
+    level_filter: LevelFilter,
+    targets: Vec<TargetEntry>,
+    hidden_targets: BTreeSet<String>,
+    source_filter: SourceFilter,
+    include: GlobSet,
+    exclude: GlobSet,
+    span_filter: Option<u64>,
+    search_state: SearchState,
+    search: Option<Search>,
+    search_error: Option<String>,
+    query_source: String,
+    query: Option<Query>,
+}
+
+impl EventFilter {
+    fn new(state: &LogsState, span_filter: Option<u64>) -> Self {
+        let mut include = GlobSetBuilder::new();
+        let mut exclude = GlobSetBuilder::new();
+        for target in &state.target_filter.targets {
+            match target.mode {
+                _ if !target.enabled => continue,
+                TargetMode::Include => include.add(target.glob.clone()),
+                TargetMode::Exclude => exclude.add(target.glob.clone()),
+            };
+        }
+
+        // an invalid search is reported instead of hiding every event.
+        let (search, search_error) = match Search::new(&state.search) {
+            Ok(search) => (search, None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        Self {
+            level_filter: state.level_filter.clone(),
+            targets: state.target_filter.targets.clone(),
+            hidden_targets: state.target_filter.hidden.clone(),
+            source_filter: state.source_filter.clone(),
+            include: include.build().unwrap(),
+            exclude: exclude.build().unwrap(),
+            span_filter,
+            search_state: state.search.clone(),
+            search,
+            search_error,
+            query_source: state.query.applied.clone(),
+            query: state.query.applied.parse().ok(),
+        }
+    }
+
+    fn is_current(&self, state: &LogsState, span_filter: Option<u64>) -> bool {
+        self.level_filter == state.level_filter
+            && self.targets == state.target_filter.targets
+            && self.hidden_targets == state.target_filter.hidden
+            && self.source_filter == state.source_filter
+            && self.span_filter == span_filter
+            && self.search_state == state.search
+            && self.query_source == state.query.applied
+    }
+
+    fn matches(&self, event: &CollectedEvent) -> bool {
+        self.level_filter.get(event.level)
+            && !self.exclude.is_match(&event.target)
+            && (self.include.is_empty() || self.include.is_match(&event.target))
+            && !self
+                .hidden_targets
+                .iter()
+                .any(|path| is_within(&event.target, path))
+            && self.source_filter.get(event.source.as_deref())
+            && self
+                .span_filter
+                .is_none_or(|id| event.spans.iter().any(|span| span.id == id))
+            && self
+                .search
+                .as_ref()
+                .is_none_or(|search| search.is_match(&short_message(event)))
+            && self.query.as_ref().is_none_or(|query| query.matches(event))
+    }
+}
+
+/// Whether `events` are ordered by `sort`, see [`compare`].
+fn is_ordered(events: &[Arc<CollectedEvent>], sort: Option<&Sort>) -> bool {
+    events.is_sorted_by(|a, b| compare(a, b, sort) != Ordering::Greater)
+}
+
+/// Sorts `events` like [`compare`], formatting each message only once when
+/// sorting by message.
+fn sort_events(events: &mut [Arc<CollectedEvent>], sort: Option<&Sort>) {
+    match sort {
+        Some(Sort { column: Column::Message, descending: false }) => {
+            events.sort_by_cached_key(|event| short_message(event));
+        }
+        Some(Sort { column: Column::Message, descending: true }) => {
+            events.sort_by_cached_key(|event| Reverse(short_message(event)));
+        }
+        _ => events.sort_by(|a, b| compare(a, b, sort)),
+    }
+}
+
+/// Merges two lists ordered by `compare`, keeping the items of `a` before
+/// equal items of `b`. The items of `b` are placed by binary search, since
+/// `b` is usually much shorter.
+fn merge<T>(a: Vec<T>, b: Vec<T>, compare: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+    let mut positions = Vec::with_capacity(b.len());
+    let mut start = 0;
+    for item in &b {
+        start += a[start..].partition_point(|x| compare(x, item) != Ordering::Greater);
+        positions.push(start);
+    }
+
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter();
+    let mut taken = 0;
+    for (item, position) in b.into_iter().zip(positions) {
+        merged.extend(a.by_ref().take(position - taken));
+        merged.push(item);
+        taken = position;
+    }
+    merged.extend(a);
+    merged
+}
+
+/// Orders events by the sorted column, or by time when not sorted, falling
+/// back to the collection order for equal values since the sorts are stable.
+/// Levels are ordered by severity.
+fn compare(a: &CollectedEvent, b: &CollectedEvent, sort: Option<&Sort>) -> Ordering {
+    let Some(sort) = sort else {
+        return a.time.cmp(&b.time);
+    };
+    let ordering = match &sort.column {
+        Column::Time => a.time.cmp(&b.time),
+        Column::Level => b.level.cmp(&a.level),
+        Column::Target => a.target.cmp(&b.target),
+        Column::Source => a.source.cmp(&b.source),
+        Column::Message => short_message(a).cmp(&short_message(b)),
+        Column::Field(key) => compare_fields(a.fields.get(key), b.fields.get(key)),
+        _ => Ordering::Equal,
+    };
+    if sort.descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Compares numbers by value and other values by their text. Events without
+/// the field come last.
+fn compare_fields(a: Option<&FieldValue>, b: Option<&FieldValue>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => a.to_string().cmp(&b.to_string()),
+        },
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{Local, TimeZone};
+    use tracing::Level;
+
+    use super::*;
+
+    fn event(second: u32, message: &str) -> CollectedEvent {
+        CollectedEvent {
+            target: "app".to_owned(),
+            level: Level::INFO,
+            fields: [("message".to_owned(), FieldValue::Str(message.to_owned()))]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>(),
+            time: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, second).unwrap(),
+            spans: Vec::new(),
+            name: "event".to_owned(),
+            module_path: None,
+            file: None,
+            line: None,
+            thread_id: None,
+            thread_name: None,
+            source: None,
+        }
+    }
+
+    fn messages(cache: &LogsCache) -> Vec<String> {
+        cache.filtered().iter().map(|event| short_message(event)).collect()
+    }
+
+    #[test]
+    fn merges_stably() {
+        let compare = |a: &(u32, &str), b: &(u32, &str)| a.0.cmp(&b.0);
+        let a = vec![(1, "a1"), (2, "a2"), (2, "a3"), (4, "a4")];
+        let b = vec![(0, "b0"), (2, "b2"), (3, "b3"), (5, "b5")];
+        let merged: Vec<_> = merge(a.clone(), b.clone(), compare)
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        assert_eq!(merged, ["b0", "a1", "a2", "a3", "b2", "b3", "a4", "b5"]);
+
+        assert_eq!(merge(Vec::new(), b.clone(), compare), b);
+        assert_eq!(merge(a.clone(), Vec::new(), compare), a);
+    }
+
+    #[test]
+    fn merges_events_out_of_order() {
+        let collector = EventCollector::default();
+        let state = LogsState::default();
+        let mut cache = LogsCache::default();
+        collector.insert(event(10, "first"));
+        collector.insert(event(20, "second"));
+        cache.update(&collector, &state, None);
+        assert!(cache.in_collection_order);
+
+        // e.g. read from a file later, placed after events of the same time.
+        collector.insert(event(5, "earlier"));
+        collector.insert(event(20, "same time"));
+        cache.update(&collector, &state, None);
+        assert_eq!(messages(&cache), ["earlier", "first", "second", "same time"]);
+        assert!(!cache.in_collection_order);
+    }
+
+    #[test]
+    fn removes_evicted_in_order() {
+        let collector = EventCollector::default().with_capacity(3);
+        let state = LogsState::default();
+        let mut cache = LogsCache::default();
+        for (second, message) in [(10, "a"), (20, "b"), (30, "c")] {
+            collector.insert(event(second, message));
+        }
+        cache.update(&collector, &state, None);
+
+        collector.insert(event(40, "d"));
+        collector.insert(event(50, "e"));
+        cache.update(&collector, &state, None);
+        assert!(cache.in_collection_order);
+        assert_eq!(messages(&cache), ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn removes_evicted_after_merge() {
+        let collector = EventCollector::default().with_capacity(3);
+        let state = LogsState::default();
+        let mut cache = LogsCache::default();
+        collector.insert(event(10, "a"));
+        collector.insert(event(20, "b"));
+        cache.update(&collector, &state, None);
+        collector.insert(event(5, "older"));
+        cache.update(&collector, &state, None);
+        assert_eq!(messages(&cache), ["older", "a", "b"]);
+
+        // the oldest collected event is no longer at the front.
+        collector.insert(event(30, "c"));
+        cache.update(&collector, &state, None);
+        assert_eq!(messages(&cache), ["older", "b", "c"]);
+        assert_eq!(cache.seqs, [1, 2, 3]);
+    }
+}
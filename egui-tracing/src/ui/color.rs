@@ -6,6 +6,30 @@ pub const INFO_COLOR: Color32 = Color32::from_rgb(78, 154, 6);
 pub const WARN_COLOR: Color32 = Color32::from_rgb(196, 160, 0);
 pub const ERROR_COLOR: Color32 = Color32::from_rgb(204, 0, 0);
 
+/// Colors of event sources, chosen to be told apart from each other and from
+/// the level colors.
+const SOURCE_COLORS: [Color32; 8] = [
+    Color32::from_rgb(52, 152, 219),
+    Color32::from_rgb(230, 126, 34),
+    Color32::from_rgb(155, 89, 182),
+    Color32::from_rgb(26, 188, 156),
+    Color32::from_rgb(231, 76, 160),
+    Color32::from_rgb(149, 165, 166),
+    Color32::from_rgb(211, 84, 0),
+    Color32::from_rgb(41, 128, 185),
+];
+
+/// Color of a [`CollectedEvent::source`](crate::tracing::CollectedEvent::source),
+/// derived from its label so that it stays the same between runs. `None`
+/// for events of this process, which use the text color.
+pub fn source_color(source: Option<&str>) -> Option<Color32> {
+    // FNV-1a, as the std hashers don't promise stable output.
+    let hash = source?.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    Some(SOURCE_COLORS[(hash % SOURCE_COLORS.len() as u64) as usize])
+}
+
 pub trait ToColor32 {
     fn to_color32(self) -> Color32;
 }
@@ -0,0 +1,108 @@
+use egui::{DragValue, RichText, TextEdit, Ui};
+use tracing::Level;
+
+use crate::tracing::{CollectorHandle, Directives, EvictionPolicy};
+use crate::ui::color::{ToColor32, ERROR_COLOR};
+use crate::ui::state::CaptureState;
+
+/// Edits what the collector captures, as opposed to the view filters which
+/// only hide already collected events.
+#[derive(Default)]
+pub struct CaptureMenuButton<'a> {
+    handle: Option<CollectorHandle>,
+    state: Option<&'a mut CaptureState>,
+}
+
+impl<'a> CaptureMenuButton<'a> {
+    pub fn handle(mut self, v: CollectorHandle) -> Self {
+        self.handle = Some(v);
+        self
+    }
+
+    pub fn state(mut self, v: &'a mut CaptureState) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let handle = self.handle.unwrap();
+        let state = self.state.unwrap();
+        ui.menu_button("Capture", |ui| {
+            ui.label("Capture Level");
+            let mut level = handle.level();
+            for option in [
+                Level::TRACE,
+                Level::DEBUG,
+                Level::INFO,
+                Level::WARN,
+                Level::ERROR,
+            ] {
+                let text =
+                    RichText::new(option.as_str()).color(option.to_color32());
+                if ui.radio_value(&mut level, option, text).changed() {
+                    handle.set_level(level);
+                }
+            }
+
+            ui.separator();
+            ui.label("Directives");
+            let input = state.directives_input.get_or_insert_with(|| {
+                handle
+                    .directives()
+                    .map(|directives| directives.to_string())
+                    .unwrap_or_default()
+            });
+            let response = ui.add(
+                TextEdit::singleline(input).hint_text("my_crate=debug,hyper=warn"),
+            );
+            let submitted = response.lost_focus()
+                && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Apply").clicked() || submitted {
+                if input.trim().is_empty() {
+                    handle.set_directives(None);
+                    state.directives_error = None;
+                } else {
+                    match input.parse::<Directives>() {
+                        Ok(directives) => {
+                            handle.set_directives(Some(directives));
+                            state.directives_error = None;
+                        }
+                        Err(err) => {
+                            state.directives_error = Some(err.to_string());
+                        }
+                    }
+                }
+            }
+            if let Some(error) = &state.directives_error {
+                ui.colored_label(ERROR_COLOR, error);
+            }
+
+            ui.separator();
+            ui.label("Retained Events");
+            let mut capacity = handle.capacity();
+            let mut limited = capacity.is_some();
+            if ui.checkbox(&mut limited, "Limit").changed() {
+                capacity = limited.then_some(10_000);
+                handle.set_capacity(capacity);
+            }
+            if let Some(mut value) = capacity {
+                let drag_value =
+                    DragValue::new(&mut value).range(1..=10_000_000).speed(10);
+                if ui.add(drag_value).changed() {
+                    handle.set_capacity(Some(value));
+                }
+                ui.label("When full");
+                let mut policy = handle.eviction_policy();
+                for (option, label) in [
+                    (EvictionPolicy::DropOldest, "Drop oldest"),
+                    (EvictionPolicy::DropLowestLevel, "Drop lowest level"),
+                    (EvictionPolicy::DropNewest, "Drop newest"),
+                ] {
+                    if ui.radio_value(&mut policy, option, label).changed() {
+                        handle.set_eviction_policy(policy);
+                    }
+                }
+            }
+        });
+    }
+}
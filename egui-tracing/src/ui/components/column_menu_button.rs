@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+
+use egui::{Button, Ui};
+
+use crate::ui::state::Column;
+
+#[derive(Default)]
+pub struct ColumnMenuButton<'a> {
+    state: Option<&'a mut Vec<Column>>,
+    field_keys: Option<&'a BTreeSet<String>>,
+}
+
+impl<'a> ColumnMenuButton<'a> {
+    pub fn state(mut self, v: &'a mut Vec<Column>) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    /// Field keys that can be added as columns.
+    pub fn field_keys(mut self, v: &'a BTreeSet<String>) -> Self {
+        self.field_keys = Some(v);
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let columns = self.state.unwrap();
+        let field_keys = self.field_keys.unwrap();
+        ui.menu_button("Columns", |ui| {
+            ui.label("Visible Columns");
+            let mut hidden = None;
+            let mut swapped = None;
+            for (index, column) in columns.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let left = ui
+                        .add_enabled(index > 0, Button::new("⬅").small())
+                        .on_hover_text("Move left");
+                    if left.clicked() {
+                        swapped = Some((index - 1, index));
+                    }
+                    let right = ui
+                        .add_enabled(index + 1 < columns.len(), Button::new("➡").small())
+                        .on_hover_text("Move right");
+                    if right.clicked() {
+                        swapped = Some((index, index + 1));
+                    }
+
+                    // at least one column stays visible.
+                    let mut visible = true;
+                    let checkbox = egui::Checkbox::new(&mut visible, column.label());
+                    if ui.add_enabled(columns.len() > 1, checkbox).changed() {
+                        hidden = Some(index);
+                    }
+                });
+            }
+            if let Some((a, b)) = swapped {
+                columns.swap(a, b);
+            }
+            if let Some(index) = hidden {
+                columns.remove(index);
+            }
+
+            let hidden: Vec<_> = Column::ALL
+                .into_iter()
+                .filter(|column| !columns.contains(column))
+                .collect();
+            if !hidden.is_empty() {
+                ui.separator();
+                ui.label("Hidden Columns");
+                for column in hidden {
+                    let mut visible = false;
+                    if ui.checkbox(&mut visible, column.label()).changed() {
+                        add_column(columns, column);
+                    }
+                }
+            }
+
+            ui.separator();
+            ui.label("Field Columns");
+            let fields: Vec<_> = field_keys
+                .iter()
+                .map(|key| Column::Field(key.clone()))
+                .filter(|column| !columns.contains(column))
+                .collect();
+            if fields.is_empty() {
+                ui.weak("No other fields seen yet");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for column in fields {
+                        let mut visible = false;
+                        if ui.checkbox(&mut visible, column.label()).changed() {
+                            add_column(columns, column);
+                        }
+                    }
+                });
+        });
+    }
+}
+
+/// Adds a column before the message, which is usually the last column and
+/// takes the remaining width.
+fn add_column(columns: &mut Vec<Column>, column: Column) {
+    let index = columns
+        .iter()
+        .position(|column| *column == Column::Message)
+        .unwrap_or(columns.len());
+    columns.insert(index, column);
+}
@@ -1,6 +1,6 @@
 use std::ops::RangeInclusive;
 
-use egui::{Ui, Vec2};
+use egui::{Align, Layout, Rect, Response, Sense, Ui, UiBuilder, Vec2};
 
 #[derive(Default, Debug, Clone)]
 pub struct CommonProps {
@@ -127,3 +127,25 @@ pub fn set_common_props(ui: &mut Ui, props: &Option<CommonProps>) {
     set_prop!(max_height, set_max_height);
     set_prop!(row_height, set_row_height);
 }
+
+/// Lays out `add_contents` left to right in exactly `width`, clipping what
+/// does not fit, so that cells of the same column line up.
+pub fn fixed_width(
+    ui: &mut Ui,
+    width: f32,
+    add_contents: impl FnOnce(&mut Ui),
+) -> Response {
+    let size = Vec2::new(width, ui.available_height());
+    let rect = Rect::from_min_size(ui.cursor().min, size);
+    let mut child = ui.new_child(
+        UiBuilder::new()
+            .max_rect(rect)
+            .layout(Layout::left_to_right(Align::Center)),
+    );
+    child.set_clip_rect(rect.intersect(ui.clip_rect()));
+    add_contents(&mut child);
+
+    let height = child.min_rect().height();
+    let rect = Rect::from_min_size(rect.min, Vec2::new(width, height));
+    ui.allocate_rect(rect, Sense::hover())
+}
@@ -0,0 +1,193 @@
+use egui::{Grid, RichText, Ui};
+
+use crate::time::DateTimeFormatExt;
+use crate::tracing::span::format_span_path;
+use crate::tracing::{CollectedEvent, FieldValue};
+use crate::ui::color::ToColor32;
+
+/// All fields, spans and metadata of an event, as copyable text.
+pub struct EventDetails<'a, T> {
+    event: Option<&'a CollectedEvent>,
+    selected: usize,
+    on_close: Option<T>,
+}
+
+impl<'a, T> Default for EventDetails<'a, T> {
+    fn default() -> Self {
+        Self {
+            event: None,
+            selected: 1,
+            on_close: None,
+        }
+    }
+}
+
+impl<'a, T> EventDetails<'a, T>
+where
+    T: FnMut(),
+{
+    pub fn event(mut self, v: &'a CollectedEvent) -> Self {
+        self.event = Some(v);
+        self
+    }
+
+    /// Number of selected events, shown when more than one is selected.
+    pub fn selected(mut self, v: usize) -> Self {
+        self.selected = v;
+        self
+    }
+
+    pub fn on_close(mut self, v: T) -> Self {
+        self.on_close = Some(v);
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let event = self.event.unwrap();
+        let mut on_close = self.on_close;
+
+        ui.horizontal(|ui| {
+            ui.strong("Event Details");
+            if self.selected > 1 {
+                ui.weak(format!("({} selected)", self.selected));
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("✖").on_hover_text("Close").clicked() {
+                    if let Some(on_close) = &mut on_close {
+                        on_close();
+                    }
+                }
+                if ui.button("Copy").on_hover_text("Copy as Text").clicked() {
+                    ui.ctx().copy_text(details_text(event));
+                }
+            });
+        });
+        ui.separator();
+
+        egui::ScrollArea::both()
+            .id_salt("event_details")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                if let Some(message) = event.fields.get("message") {
+                    ui.label(RichText::new(message.to_string()).monospace());
+                    ui.separator();
+                }
+
+                section(ui, "Fields", |ui| {
+                    for (key, value) in &event.fields {
+                        if key == "message" {
+                            continue;
+                        }
+                        row(ui, key, value.to_string());
+                        if let FieldValue::Error { sources, .. } = value {
+                            for source in sources {
+                                row(ui, "caused by", source.clone());
+                            }
+                        }
+                    }
+                });
+
+                if !event.spans.is_empty() {
+                    ui.add_space(8.0);
+                    section(ui, "Spans", |ui| {
+                        for (depth, span) in event.spans.iter().enumerate() {
+                            let path = format_span_path(std::slice::from_ref(span), true);
+                            row(ui, &depth.to_string(), path);
+                        }
+                    });
+                }
+
+                ui.add_space(8.0);
+                section(ui, "Metadata", |ui| {
+                    ui.label("level");
+                    ui.colored_label(event.level.to_color32(), event.level.as_str());
+                    ui.end_row();
+                    row(ui, "target", event.target.clone());
+                    row(ui, "name", event.name.clone());
+                    if let Some(module_path) = &event.module_path {
+                        row(ui, "module", module_path.clone());
+                    }
+                    if let Some(location) = event.location() {
+                        row(ui, "location", location);
+                    }
+                    if let Some(thread_name) = &event.thread_name {
+                        row(ui, "thread", thread_name.clone());
+                    }
+                    if let Some(thread_id) = &event.thread_id {
+                        row(ui, "thread id", thread_id.clone());
+                    }
+                    if let Some(source) = &event.source {
+                        row(ui, "source", source.clone());
+                    }
+                    row(ui, "time", event.time.format_detailed());
+                    row(ui, "utc", event.time.naive_utc().to_string());
+                });
+            });
+    }
+}
+
+fn section(ui: &mut Ui, title: &str, add_contents: impl FnOnce(&mut Ui)) {
+    ui.strong(title);
+    Grid::new(title)
+        .num_columns(2)
+        .striped(true)
+        .show(ui, add_contents);
+}
+
+fn row(ui: &mut Ui, key: &str, value: String) {
+    ui.label(RichText::new(key).weak());
+    ui.label(RichText::new(value).monospace());
+    ui.end_row();
+}
+
+/// Pretty-printed text of everything shown in the details pane.
+pub fn details_text(event: &CollectedEvent) -> String {
+    let mut text = format!(
+        "{} {} {}",
+        event.time.format_detailed(),
+        event.level,
+        event.target
+    );
+    if let Some(message) = event.fields.get("message") {
+        text.push_str(&format!("\n{}", message));
+    }
+
+    text.push_str("\n\nfields:");
+    for (key, value) in &event.fields {
+        if key == "message" {
+            continue;
+        }
+        text.push_str(&format!("\n  {}: {}", key, value));
+        if let FieldValue::Error { sources, .. } = value {
+            for source in sources {
+                text.push_str(&format!("\n    caused by: {}", source));
+            }
+        }
+    }
+
+    if !event.spans.is_empty() {
+        text.push_str("\n\nspans:");
+        for span in &event.spans {
+            text.push_str(&format!(
+                "\n  {}",
+                format_span_path(std::slice::from_ref(span), true)
+            ));
+        }
+    }
+
+    text.push_str("\n\nmetadata:");
+    text.push_str(&format!("\n  name: {}", event.name));
+    if let Some(module_path) = &event.module_path {
+        text.push_str(&format!("\n  module: {}", module_path));
+    }
+    if let Some(location) = event.location() {
+        text.push_str(&format!("\n  location: {}", location));
+    }
+    if let Some(thread) = event.thread() {
+        text.push_str(&format!("\n  thread: {}", thread));
+    }
+    if let Some(source) = &event.source {
+        text.push_str(&format!("\n  source: {}", source));
+    }
+    text
+}
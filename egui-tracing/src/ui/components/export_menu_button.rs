@@ -0,0 +1,89 @@
+use std::io;
+use std::sync::Arc;
+
+use egui::Ui;
+
+use crate::tracing::export::export;
+use crate::tracing::{CollectedEvent, ExportFormat};
+use crate::ui::export::LogsExporter;
+
+#[derive(Default)]
+pub struct ExportMenuButton<'a> {
+    events: Option<&'a [Arc<CollectedEvent>]>,
+    selected: Option<&'a [Arc<CollectedEvent>]>,
+    exporter: Option<&'a dyn LogsExporter>,
+}
+
+impl<'a> ExportMenuButton<'a> {
+    /// The events shown in the table.
+    pub fn events(mut self, v: &'a [Arc<CollectedEvent>]) -> Self {
+        self.events = Some(v);
+        self
+    }
+
+    pub fn selected(mut self, v: &'a [Arc<CollectedEvent>]) -> Self {
+        self.selected = Some(v);
+        self
+    }
+
+    pub fn exporter(mut self, v: Option<&'a dyn LogsExporter>) -> Self {
+        self.exporter = v;
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let events = self.events.unwrap();
+        let selected = self.selected.unwrap_or_default();
+        let action = if self.exporter.is_some() { "Save" } else { "Copy" };
+        let error_id = ui.id().with("export_error");
+
+        let menu = ui.menu_button("Export", |ui| {
+            let scopes = [("Shown", events), ("Selected", selected)];
+            for (name, events) in scopes {
+                ui.label(format!("{} Events ({})", name, events.len()));
+                for format in ExportFormat::ALL {
+                    let text = format!("{} as {}", action, format.label());
+                    let button = egui::Button::new(text);
+                    if ui.add_enabled(!events.is_empty(), button).clicked() {
+                        match self.export(ui, events, format) {
+                            Ok(()) => ui.close_menu(),
+                            // the menu stays open to show the error.
+                            Err(err) => ui.data_mut(|data| {
+                                data.insert_temp(error_id, format!("Export failed: {}", err))
+                            }),
+                        }
+                    }
+                }
+            }
+
+            if let Some(error) = ui.data(|data| data.get_temp::<String>(error_id)) {
+                ui.separator();
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+        if menu.inner.is_none() {
+            ui.data_mut(|data| data.remove::<String>(error_id));
+        }
+    }
+
+    fn export(
+        &self,
+        ui: &Ui,
+        events: &[Arc<CollectedEvent>],
+        format: ExportFormat,
+    ) -> io::Result<()> {
+        let events = events.iter().map(|event| &**event);
+        match self.exporter {
+            Some(exporter) => match exporter.writer(format) {
+                Some(writer) => export(events, format, writer),
+                None => Ok(()),
+            },
+            None => {
+                let mut buffer = Vec::new();
+                export(events, format, &mut buffer).map(|()| {
+                    ui.ctx().copy_text(String::from_utf8_lossy(&buffer).into_owned());
+                })
+            }
+        }
+    }
+}
@@ -1,13 +1,22 @@
 use egui::Ui;
 
+pub mod capture_menu_button;
+pub mod column_menu_button;
 pub mod common;
 pub mod constants;
+pub mod event_details;
+pub mod export_menu_button;
 pub mod level_menu_button;
+pub mod query_bar;
+pub mod search_box;
+pub mod sort_button;
+pub mod source_menu_button;
 pub mod table;
 pub mod table_cell;
 pub mod table_header;
 pub mod target_menu_button;
 pub mod target_menu_item;
+pub mod target_tree;
 
 pub trait ChildFn: FnMut(&mut Ui) {}
 
@@ -0,0 +1,63 @@
+use egui::{TextEdit, Ui};
+
+use crate::ui::color::ERROR_COLOR;
+use crate::ui::state::QueryState;
+
+#[derive(Default)]
+pub struct QueryBar<'a> {
+    state: Option<&'a mut QueryState>,
+}
+
+impl<'a> QueryBar<'a> {
+    pub fn state(mut self, v: &'a mut QueryState) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    /// Lays out the query bar for a right-to-left toolbar.
+    pub fn show(self, ui: &mut Ui) {
+        let state = self.state.unwrap();
+
+        ui.add_enabled_ui(!state.history.is_empty(), |ui| {
+            ui.menu_button("History", |ui| {
+                for query in state.history.clone().iter().rev() {
+                    if ui.button(query).clicked() {
+                        state.input = query.clone();
+                        state.apply();
+                        ui.close_menu();
+                    }
+                }
+                ui.separator();
+                if ui.button("Clear History").clicked() {
+                    state.history.clear();
+                    ui.close_menu();
+                }
+            });
+        });
+
+        let response = ui
+            .add(
+                TextEdit::singleline(&mut state.input)
+                    .hint_text("Query")
+                    .desired_width(240.0),
+            )
+            .on_hover_text(
+                "example: level>=warn and target:my_app::* and user_id=42 \
+                 and message~\"timeout\"",
+            );
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            state.apply();
+        }
+        if response.changed() {
+            state.error = None;
+        }
+
+        if let Some(error) = &state.error {
+            ui.colored_label(ERROR_COLOR, format!("col {}", error.position + 1))
+                .on_hover_text(error.to_string());
+            ui.colored_label(ERROR_COLOR, &error.reason);
+        } else if state.input.trim() != state.applied {
+            ui.weak("press Enter to apply");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use egui::{TextEdit, Ui};
+
+use crate::ui::color::ERROR_COLOR;
+use crate::ui::state::SearchState;
+
+#[derive(Default)]
+pub struct SearchBox<'a> {
+    state: Option<&'a mut SearchState>,
+    error: Option<String>,
+}
+
+impl<'a> SearchBox<'a> {
+    pub fn state(mut self, v: &'a mut SearchState) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    /// Error to show for an invalid search, e.g. a malformed regex.
+    pub fn error(mut self, v: Option<String>) -> Self {
+        self.error = v;
+        self
+    }
+
+    /// Lays out the search box for a right-to-left toolbar.
+    pub fn show(self, ui: &mut Ui) {
+        let state = self.state.unwrap();
+        ui.toggle_value(&mut state.regex, ".*")
+            .on_hover_text("Use Regular Expression");
+        ui.toggle_value(&mut state.case_sensitive, "Aa")
+            .on_hover_text("Match Case");
+        ui.add(
+            TextEdit::singleline(&mut state.input)
+                .hint_text("Search")
+                .desired_width(160.0),
+        );
+        if let Some(error) = self.error {
+            ui.colored_label(ERROR_COLOR, "invalid regex")
+                .on_hover_text(error);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use egui::Ui;
+
+use crate::ui::state::{Column, Sort};
+
+/// Cycles the sort of a column between ascending, descending and unsorted.
+#[derive(Default)]
+pub struct SortButton<'a> {
+    state: Option<&'a mut Option<Sort>>,
+    column: Option<Column>,
+}
+
+impl<'a> SortButton<'a> {
+    pub fn state(mut self, v: &'a mut Option<Sort>) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    pub fn column(mut self, v: Column) -> Self {
+        self.column = Some(v);
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let sort = self.state.unwrap();
+        let column = self.column.unwrap();
+        let descending = sort
+            .as_ref()
+            .filter(|sort| sort.column == column)
+            .map(|sort| sort.descending);
+
+        let icon = match descending {
+            Some(false) => "⬆",
+            Some(true) => "⬇",
+            None => "↕",
+        };
+        if ui
+            .small_button(icon)
+            .on_hover_text(format!("Sort by {}", column.label()))
+            .clicked()
+        {
+            *sort = match descending {
+                None => Some(Sort {
+                    column,
+                    descending: false,
+                }),
+                Some(false) => Some(Sort {
+                    column,
+                    descending: true,
+                }),
+                Some(true) => None,
+            };
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeSet;
+
+use egui::{RichText, Ui};
+
+use super::common::{set_common_props, CommonProps};
+use crate::ui::color::source_color;
+use crate::ui::source_label;
+use crate::ui::state::SourceFilter;
+
+#[derive(Default)]
+pub struct SourceMenuButton<'a> {
+    state: Option<&'a mut SourceFilter>,
+    sources: Option<&'a BTreeSet<Option<String>>>,
+    common_props: Option<CommonProps>,
+}
+
+impl<'a> SourceMenuButton<'a> {
+    pub fn state(mut self, v: &'a mut SourceFilter) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    /// The sources events were seen from.
+    pub fn sources(mut self, v: &'a BTreeSet<Option<String>>) -> Self {
+        self.sources = Some(v);
+        self
+    }
+
+    pub fn show(mut self, ui: &mut Ui) {
+        let state = self.state.as_mut().unwrap();
+        // hidden sources stay listed after a clear, to show them again.
+        let sources: BTreeSet<_> = self
+            .sources
+            .unwrap()
+            .union(&state.hidden)
+            .cloned()
+            .collect();
+        ui.menu_button("Source", |ui| {
+            set_common_props(ui, &self.common_props);
+            ui.label("Source Filter");
+            if sources.is_empty() {
+                ui.weak("No events yet");
+            }
+            for source in sources {
+                let mut shown = !state.hidden.contains(&source);
+                let mut text = RichText::new(source_label(source.as_deref()));
+                if let Some(color) = source_color(source.as_deref()) {
+                    text = text.color(color);
+                }
+                if ui.checkbox(&mut shown, text).changed() {
+                    if shown {
+                        state.hidden.remove(&source);
+                    } else {
+                        state.hidden.insert(source);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -1,36 +1,127 @@
+use std::collections::HashSet;
 use std::marker::PhantomData;
-use std::slice::Iter;
 
-use egui::{Response, Ui};
+use egui::{vec2, Key, Modifiers, Rect, Response, RichText, Sense, Shape, Ui};
 
 use super::constants::SEPARATOR_SPACING;
 use super::ChildFn;
 
-pub struct Table<OnClearFn, HeaderFn, RowFn, Item> {
+pub struct Table<'a, OnClearFn, HeaderFn, ToolbarFn, RowFn, Item> {
     row_height: Option<f32>,
     on_clear: Option<OnClearFn>,
     header: Option<HeaderFn>,
+    toolbar: Option<ToolbarFn>,
     row: Option<RowFn>,
+    selection: Option<(&'a mut Selection<Item>, ItemKeyFn<Item>)>,
+    pause: Option<(&'a mut bool, usize)>,
     _marker: PhantomData<Item>,
 }
 
-impl<OnClearFn, HeaderFn, RowFn, Item> Default for Table<OnClearFn, HeaderFn, RowFn, Item> {
+/// Identifies the item a row shows, e.g. by its address.
+pub type ItemKeyFn<Item> = fn(&Item) -> usize;
+
+/// Rows selected in a [`Table`], kept across frames by the caller.
+#[derive(Debug, Clone)]
+pub struct Selection<Item> {
+    items: Vec<Item>,
+    /// Keys of `items`, to look up selected rows quickly.
+    keys: HashSet<usize>,
+    /// Start of a shift-click range.
+    anchor: Option<Item>,
+    /// The most recently selected row.
+    cursor: Option<Item>,
+}
+
+impl<Item> Default for Selection<Item> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            keys: HashSet::new(),
+            anchor: None,
+            cursor: None,
+        }
+    }
+}
+
+impl<Item: Clone> Selection<Item> {
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn cursor(&self) -> Option<&Item> {
+        self.cursor.as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn contains(&self, item: &Item, key: ItemKeyFn<Item>) -> bool {
+        self.keys.contains(&key(item))
+    }
+
+    /// Selects the row at `index` like a click with `modifiers` would: shift
+    /// extends the selection from the anchor, ctrl/cmd toggles the row.
+    fn select(
+        &mut self,
+        values: &[Item],
+        index: usize,
+        modifiers: Modifiers,
+        key: ItemKeyFn<Item>,
+    ) {
+        let item = &values[index];
+        let item_key = key(item);
+        let anchor = self.anchor.as_ref().and_then(|anchor| {
+            let anchor = key(anchor);
+            values.iter().position(|value| key(value) == anchor)
+        });
+
+        if let (true, Some(anchor)) = (modifiers.shift, anchor) {
+            let range = anchor.min(index)..=anchor.max(index);
+            self.items = values[range].to_vec();
+            self.keys = self.items.iter().map(key).collect();
+        } else if modifiers.command {
+            if self.keys.remove(&item_key) {
+                self.items.retain(|selected| key(selected) != item_key);
+            } else {
+                self.keys.insert(item_key);
+                self.items.push(item.clone());
+            }
+            self.anchor = Some(item.clone());
+        } else {
+            self.items = vec![item.clone()];
+            self.keys = HashSet::from([item_key]);
+            self.anchor = Some(item.clone());
+        }
+        self.cursor = Some(item.clone());
+    }
+}
+
+impl<OnClearFn, HeaderFn, ToolbarFn, RowFn, Item> Default
+    for Table<'_, OnClearFn, HeaderFn, ToolbarFn, RowFn, Item>
+{
     fn default() -> Self {
         Self {
             row_height: None,
             on_clear: None,
             header: None,
+            toolbar: None,
             row: None,
+            selection: None,
+            pause: None,
             _marker: PhantomData,
         }
     }
 }
 
-impl<OnClearFn, HeaderFn, RowFn, Item> Table<OnClearFn, HeaderFn, RowFn, Item>
+impl<'a, OnClearFn, HeaderFn, ToolbarFn, RowFn, Item>
+    Table<'a, OnClearFn, HeaderFn, ToolbarFn, RowFn, Item>
 where
     OnClearFn: FnMut(),
     HeaderFn: ChildFn,
+    ToolbarFn: ChildFn,
     RowFn: FnMut(&mut Ui, &Item),
+    Item: Clone,
 {
     pub fn row_height(mut self, v: f32) -> Self {
         self.row_height = Some(v);
@@ -47,64 +138,251 @@ where
         self
     }
 
+    /// Extra toolbar content, laid out right-to-left before the built-in
+    /// buttons.
+    pub fn toolbar(mut self, v: ToolbarFn) -> Self {
+        self.toolbar = Some(v);
+        self
+    }
+
     pub fn row(mut self, v: RowFn) -> Self {
         self.row = Some(v);
         self
     }
 
-    pub fn show(self, ui: &mut Ui, values: Iter<&Item>) -> Response {
+    /// Makes rows selectable with the mouse (click, shift-click,
+    /// ctrl/cmd-click) and the arrow keys once a row was clicked.
+    pub fn selection(
+        mut self,
+        selection: &'a mut Selection<Item>,
+        key: ItemKeyFn<Item>,
+    ) -> Self {
+        self.selection = Some((selection, key));
+        self
+    }
+
+    /// Adds a Pause toggle, for the caller to stop updating the values while
+    /// `paused`. `new_rows` is the number of rows held back, shown as a badge.
+    /// Resuming, or clearing, scrolls back to the bottom.
+    pub fn pause(mut self, paused: &'a mut bool, new_rows: usize) -> Self {
+        self.pause = Some((paused, new_rows));
+        self
+    }
+
+    pub fn show(mut self, ui: &mut Ui, values: &[Item]) -> Response {
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
                 ui.horizontal(|ui| {
                     (self.header.unwrap())(ui);
                 });
 
-                ui.add_space(ui.available_width() - 130.0);
-
-                if ui
-                    .button("To Bottom")
-                    .on_hover_text("Scroll to Bottom")
-                    .clicked()
-                {
-                    ui.scroll_to_rect(
-                        egui::Rect {
-                            min: egui::Pos2 { x: 0.0, y: 0.0 },
-                            max: egui::Pos2 {
-                                x: f32::MAX,
-                                y: f32::MAX,
-                            },
-                        },
-                        Some(egui::Align::Max),
-                    );
-                }
-
-                ui.separator();
-
-                if ui.button("Clear").on_hover_text("Clear Events").clicked() {
-                    (self.on_clear.unwrap())();
-                }
+                ui.with_layout(
+                    egui::Layout::right_to_left(egui::Align::Center),
+                    |ui| {
+                        if ui
+                            .button("Clear")
+                            .on_hover_text("Clear Events")
+                            .clicked()
+                        {
+                            (self.on_clear.unwrap())();
+                            if let Some((selection, _)) = &mut self.selection {
+                                selection.clear();
+                            }
+                            if let Some((paused, _)) = &mut self.pause {
+                                **paused = false;
+                            }
+                        }
+
+                        ui.separator();
+
+                        if ui
+                            .button("To Bottom")
+                            .on_hover_text("Scroll to Bottom")
+                            .clicked()
+                        {
+                            scroll_to_bottom(ui);
+                        }
+
+                        if let Some((paused, new_rows)) = &mut self.pause {
+                            pause_ui(ui, paused, *new_rows);
+                        }
+
+                        if let Some(mut toolbar) = self.toolbar {
+                            ui.separator();
+                            toolbar(ui);
+                        }
+                    },
+                );
             });
 
             ui.separator();
 
             let mut row = self.row.unwrap();
-            egui::ScrollArea::vertical()
+            let row_height = self.row_height.unwrap() + SEPARATOR_SPACING;
+            let rows_id = ui.id().with("rows");
+            let mut row_clicked = false;
+            let scroll_to = self.selection.as_mut().and_then(|(selection, key)| {
+                keyboard_input(ui, rows_id, selection, *key, values)
+            });
+
+            let response = egui::ScrollArea::vertical()
                 .auto_shrink([true, false])
-                .stick_to_bottom(true)
-                .show_rows(
-                    ui,
-                    self.row_height.unwrap() + SEPARATOR_SPACING,
-                    values.len(),
-                    |ui, range| {
-                        for value in values.skip(range.start).take(range.len()) {
-                            ui.horizontal(|ui| {
+                .stick_to_bottom(!self.pause.as_ref().is_some_and(|(paused, _)| **paused))
+                .show_rows(ui, row_height, values.len(), |ui, range| {
+                    if let Some(index) = scroll_to {
+                        let offset = (index as f32 - range.start as f32) * row_height;
+                        let top = ui.cursor().top() + offset;
+                        let rect = Rect::from_min_size(
+                            egui::pos2(ui.cursor().left(), top),
+                            vec2(1.0, row_height),
+                        );
+                        ui.scroll_to_rect(rect, None);
+                    }
+
+                    for (index, value) in values[range.clone()].iter().enumerate() {
+                        let index = range.start + index;
+                        let background = ui.painter().add(Shape::Noop);
+                        let response = ui
+                            .horizontal(|ui| {
                                 row(ui, value);
-                            });
-                            ui.separator();
+                            })
+                            .response;
+
+                        if let Some((selection, key)) = &mut self.selection {
+                            let rect = Rect::from_x_y_ranges(
+                                ui.max_rect().x_range(),
+                                response.rect.y_range(),
+                            )
+                            .expand2(vec2(0.0, SEPARATOR_SPACING / 2.0));
+                            let interact =
+                                ui.interact(rect, rows_id.with(index), Sense::click());
+                            if interact.clicked() {
+                                let modifiers = ui.input(|i| i.modifiers);
+                                selection.select(values, index, modifiers, *key);
+                                row_clicked = true;
+                            }
+
+                            let fill = if selection.contains(value, *key) {
+                                Some(ui.visuals().selection.bg_fill)
+                            } else if interact.hovered() {
+                                Some(ui.visuals().widgets.hovered.weak_bg_fill)
+                            } else {
+                                None
+                            };
+                            if let Some(fill) = fill {
+                                ui.painter()
+                                    .set(background, Shape::rect_filled(rect, 0.0, fill));
+                            }
                         }
-                    },
-                )
+                        ui.separator();
+                    }
+                });
+
+            // the arrow keys move the selection until clicking elsewhere.
+            if ui.input(|i| i.pointer.any_click()) {
+                ui.data_mut(|data| data.insert_temp(rows_id, row_clicked));
+            }
+            response
         })
         .response
     }
 }
+
+fn scroll_to_bottom(ui: &Ui) {
+    ui.scroll_to_rect(
+        egui::Rect {
+            min: egui::Pos2 { x: 0.0, y: 0.0 },
+            max: egui::Pos2 {
+                x: f32::MAX,
+                y: f32::MAX,
+            },
+        },
+        Some(egui::Align::Max),
+    );
+}
+
+/// The Pause/Resume toggle, with the number of held back rows while paused.
+/// Laid out right-to-left.
+fn pause_ui(ui: &mut Ui, paused: &mut bool, new_rows: usize) {
+    if !*paused {
+        if ui
+            .button("⏸ Pause")
+            .on_hover_text("Freeze the shown events while collecting continues")
+            .clicked()
+        {
+            *paused = true;
+        }
+        return;
+    }
+
+    if ui
+        .button("▶ Resume")
+        .on_hover_text("Show the live events again")
+        .clicked()
+    {
+        *paused = false;
+        scroll_to_bottom(ui);
+    }
+    if new_rows > 0 {
+        egui::Frame::none()
+            .fill(ui.visuals().selection.bg_fill)
+            .rounding(8.0)
+            .inner_margin(egui::Margin::symmetric(6.0, 1.0))
+            .show(ui, |ui| {
+                let text = RichText::new(format!("{} new", new_rows))
+                    .small()
+                    .color(ui.visuals().strong_text_color());
+                ui.label(text)
+            })
+            .inner
+            .on_hover_text("Events collected while paused, before filtering");
+    }
+}
+
+/// Moves the selection with the arrow keys after a row was clicked. Returns
+/// the index of the row to scroll to.
+fn keyboard_input<Item: Clone>(
+    ui: &Ui,
+    rows_id: egui::Id,
+    selection: &mut Selection<Item>,
+    key: ItemKeyFn<Item>,
+    values: &[Item],
+) -> Option<usize> {
+    let active = ui.data(|data| data.get_temp::<bool>(rows_id));
+    if active != Some(true)
+        || ui.ctx().wants_keyboard_input()
+        || values.is_empty()
+    {
+        return None;
+    }
+
+    let (up, down, escape, modifiers) = ui.input(|i| {
+        (
+            i.key_pressed(Key::ArrowUp),
+            i.key_pressed(Key::ArrowDown),
+            i.key_pressed(Key::Escape),
+            i.modifiers,
+        )
+    });
+    if escape {
+        selection.clear();
+        return None;
+    }
+
+    let cursor = selection.cursor().and_then(|cursor| {
+        let cursor = key(cursor);
+        values.iter().position(|value| key(value) == cursor)
+    });
+    let index = match (cursor, up, down) {
+        (Some(cursor), true, false) => cursor.saturating_sub(1),
+        (Some(cursor), false, true) => (cursor + 1).min(values.len() - 1),
+        (None, true, _) | (None, _, true) => values.len() - 1,
+        _ => return None,
+    };
+    let modifiers = Modifiers {
+        shift: modifiers.shift,
+        ..Modifiers::NONE
+    };
+    selection.select(values, index, modifiers, key);
+    Some(index)
+}
@@ -1,6 +1,6 @@
 use egui::{Response, Ui};
 
-use super::common::{set_common_props, CommonProps};
+use super::common::{fixed_width, set_common_props, CommonProps};
 use super::table_header;
 use super::ChildFn;
 
@@ -10,6 +10,7 @@ where
     T: ChildFn,
 {
     common_props: Option<CommonProps>,
+    width: Option<f32>,
     children: Option<T>,
 }
 
@@ -20,6 +21,7 @@ where
     fn default() -> Self {
         Self {
             common_props: None,
+            width: None,
             children: None,
         }
     }
@@ -34,17 +36,29 @@ where
         self
     }
 
+    /// Fixed width of the cell, matching its column's header.
+    pub fn width(mut self, v: f32) -> Self {
+        self.width = Some(v);
+        self
+    }
+
     pub fn children(mut self, v: T) -> Self {
         self.children = Some(v);
         self
     }
     pub fn show(self, ui: &mut Ui) -> Response {
-        ui.horizontal(|ui| {
-            set_common_props(ui, &self.common_props);
+        let common_props = self.common_props;
+        let mut children = self.children.unwrap();
+        let add_contents = |ui: &mut Ui| {
+            set_common_props(ui, &common_props);
             ui.add_space(table_header::PADDING_LEFT * 2.0 + 10.0);
 
-            (self.children.unwrap())(ui);
-        })
-        .response
+            children(ui);
+        };
+
+        match self.width {
+            Some(width) => fixed_width(ui, width, add_contents),
+            None => ui.horizontal(add_contents).response,
+        }
     }
 }
@@ -1,25 +1,32 @@
-use egui::{vec2, Response, Sense, Ui};
+use std::cell::Cell;
 
-use super::common::{set_common_props, CommonProps};
+use egui::{vec2, CursorIcon, Rect, Response, Sense, Ui};
+
+use super::common::{fixed_width, set_common_props, CommonProps};
 use super::ChildFn;
 
 pub static PADDING_LEFT: f32 = 4.0;
 
-pub struct TableHeader<T> {
+/// Columns can't be resized narrower than this.
+pub static MIN_WIDTH: f32 = 40.0;
+
+pub struct TableHeader<'a, T> {
     common_props: Option<CommonProps>,
+    width: Option<&'a Cell<f32>>,
     children: Option<T>,
 }
 
-impl<T> Default for TableHeader<T> {
+impl<T> Default for TableHeader<'_, T> {
     fn default() -> Self {
         Self {
             common_props: None,
+            width: None,
             children: None,
         }
     }
 }
 
-impl<T> TableHeader<T>
+impl<'a, T> TableHeader<'a, T>
 where
     T: ChildFn,
 {
@@ -28,14 +35,24 @@ where
         self
     }
 
+    /// Gives the column a fixed width that can be changed by dragging the
+    /// header's right edge. The cells of the column should use the same
+    /// width, read after the header was shown.
+    pub fn width(mut self, v: &'a Cell<f32>) -> Self {
+        self.width = Some(v);
+        self
+    }
+
     pub fn children(mut self, v: T) -> Self {
         self.children = Some(v);
         self
     }
 
     pub fn show(self, ui: &mut Ui) -> Response {
-        ui.horizontal(|ui| {
-            set_common_props(ui, &self.common_props);
+        let common_props = self.common_props;
+        let mut children = self.children.unwrap();
+        let add_contents = |ui: &mut Ui| {
+            set_common_props(ui, &common_props);
             let available_space = ui.available_size_before_wrap();
             let size = vec2(PADDING_LEFT, available_space.y);
             let (rect, response) = ui.allocate_at_least(size, Sense::hover());
@@ -45,8 +62,32 @@ where
                 painter.vline(rect.left(), rect.top()..=rect.bottom(), stroke);
             }
 
-            (self.children.unwrap())(ui)
-        })
-        .response
+            children(ui)
+        };
+
+        match self.width {
+            Some(width) => {
+                let response = fixed_width(ui, width.get(), add_contents);
+                resize_handle(ui, &response, width);
+                response
+            }
+            None => ui.horizontal(add_contents).response,
+        }
+    }
+}
+
+/// Lets the column be resized by dragging the space after `header`.
+fn resize_handle(ui: &mut Ui, header: &Response, width: &Cell<f32>) {
+    let x = header.rect.right() + ui.spacing().item_spacing.x / 2.0;
+    let rect = Rect::from_x_y_ranges(x - 3.0..=x + 3.0, header.rect.y_range());
+    let response = ui.interact(rect, header.id.with("resize"), Sense::drag());
+
+    if let Some(pos) = response.interact_pointer_pos().filter(|_| response.dragged()) {
+        width.set((pos.x - header.rect.left()).max(MIN_WIDTH));
+    }
+    if response.hovered() || response.dragged() {
+        ui.ctx().set_cursor_icon(CursorIcon::ResizeColumn);
+        let stroke = ui.visuals().widgets.active.fg_stroke;
+        ui.painter().vline(x, header.rect.y_range(), stroke);
     }
 }
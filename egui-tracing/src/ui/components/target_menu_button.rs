@@ -2,11 +2,14 @@ use egui::Ui;
 use globset::Glob;
 
 use super::target_menu_item::TargetMenuItem;
-use crate::ui::state::TargetFilter;
+use super::target_tree::TargetTree;
+use crate::tracing::EventCollector;
+use crate::ui::state::{TargetEntry, TargetFilter, TargetMode};
 
 #[derive(Default)]
 pub struct TargetMenuButton<'a> {
     state: Option<&'a mut TargetFilter>,
+    collector: Option<&'a EventCollector>,
 }
 
 impl<'a> TargetMenuButton<'a> {
@@ -15,36 +18,85 @@ impl<'a> TargetMenuButton<'a> {
         self
     }
 
+    /// Collector whose targets are shown in the target tree.
+    pub fn collector(mut self, v: &'a EventCollector) -> Self {
+        self.collector = Some(v);
+        self
+    }
+
     pub fn show(self, ui: &mut Ui) {
         let state = self.state.unwrap();
         ui.menu_button("Target", |ui| {
             ui.label("Target Filter");
 
+            // nothing to add while the input is empty or invalid.
+            let glob = Some(state.input.trim())
+                .filter(|input| !input.is_empty())
+                .map(Glob::new);
             let (input, add_button) = ui
                 .horizontal(|ui| {
                     let input = ui
                         .text_edit_singleline(&mut state.input)
                         .on_hover_text("example: eframe::*");
-                    let button = ui.button("Add");
+                    egui::ComboBox::from_id_salt("target_mode")
+                        .selected_text(state.mode.label())
+                        .width(70.0)
+                        .show_ui(ui, |ui| {
+                            for mode in [TargetMode::Include, TargetMode::Exclude] {
+                                ui.selectable_value(&mut state.mode, mode, mode.label());
+                            }
+                        });
+                    let button = ui.add_enabled(
+                        matches!(glob, Some(Ok(_))),
+                        egui::Button::new("Add"),
+                    );
                     (input, button)
                 })
                 .inner;
 
-            if add_button.clicked()
-                || (input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
-            {
-                state.targets.push(Glob::new(&state.input).unwrap());
-                state.input = "".to_owned();
+            match glob {
+                Some(Ok(glob))
+                    if add_button.clicked()
+                        || (input.lost_focus()
+                            && ui.input(|i| i.key_pressed(egui::Key::Enter))) =>
+                {
+                    state.targets.push(TargetEntry {
+                        glob,
+                        mode: state.mode,
+                        enabled: true,
+                    });
+                    state.input = "".to_owned();
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.kind().to_string());
+                }
+                _ => {}
             }
 
-            for (i, target) in state.targets.clone().iter().enumerate() {
+            let mut deleted = None;
+            for (i, target) in state.targets.iter_mut().enumerate() {
                 TargetMenuItem::default()
-                    .on_clicked(|| {
-                        state.targets.remove(i);
-                    })
+                    .on_delete(|| deleted = Some(i))
                     .target(target)
                     .show(ui);
             }
+            if let Some(i) = deleted {
+                state.targets.remove(i);
+            }
+
+            if let Some(collector) = self.collector {
+                ui.separator();
+                ui.label("Target Tree");
+                egui::ScrollArea::vertical()
+                    .id_salt("target_tree")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        TargetTree::default()
+                            .state(state)
+                            .counts(collector.target_counts())
+                            .show(ui);
+                    });
+            }
         });
     }
 }
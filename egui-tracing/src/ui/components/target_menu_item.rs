@@ -1,18 +1,18 @@
 use egui::Ui;
-use globset::Glob;
 
 use crate::string::Ellipse;
+use crate::ui::state::{TargetEntry, TargetMode};
 
 pub struct TargetMenuItem<'a, T> {
-    target: Option<&'a Glob>,
-    on_clicked: Option<T>,
+    target: Option<&'a mut TargetEntry>,
+    on_delete: Option<T>,
 }
 
 impl<'a, T> Default for TargetMenuItem<'a, T> {
     fn default() -> Self {
         Self {
             target: None,
-            on_clicked: None,
+            on_delete: None,
         }
     }
 }
@@ -21,25 +21,48 @@ impl<'a, T> TargetMenuItem<'a, T>
 where
     T: FnMut(),
 {
-    pub fn target(mut self, v: &'a Glob) -> Self {
+    pub fn target(mut self, v: &'a mut TargetEntry) -> Self {
         self.target = Some(v);
         self
     }
 
-    pub fn on_clicked(mut self, v: T) -> Self {
-        self.on_clicked = Some(v);
+    pub fn on_delete(mut self, v: T) -> Self {
+        self.on_delete = Some(v);
         self
     }
 
     pub fn show(self, ui: &mut Ui) {
         ui.separator();
-        let pattern = self.target.unwrap().glob().to_owned();
+        let target = self.target.unwrap();
+        let pattern = target.glob.glob().to_owned();
         ui.horizontal(|ui| {
-            ui.label(pattern.truncate_graphemes(18))
-                .on_hover_text(pattern);
+            ui.checkbox(&mut target.enabled, "")
+                .on_hover_text("Enable or disable this entry");
+            let mode = match target.mode {
+                TargetMode::Include => "+",
+                TargetMode::Exclude => "-",
+            };
+            if ui
+                .button(mode)
+                .on_hover_text(format!(
+                    "{} matching targets, click to toggle",
+                    target.mode.label()
+                ))
+                .clicked()
+            {
+                target.mode = match target.mode {
+                    TargetMode::Include => TargetMode::Exclude,
+                    TargetMode::Exclude => TargetMode::Include,
+                };
+            }
+            ui.add_enabled(
+                target.enabled,
+                egui::Label::new(pattern.truncate_graphemes(18)),
+            )
+            .on_hover_text(pattern);
             ui.add_space(ui.available_width() - 43.0);
             if ui.button("Delete").clicked() {
-                self.on_clicked.unwrap()();
+                self.on_delete.unwrap()();
             }
         });
     }
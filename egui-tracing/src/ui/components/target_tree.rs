@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use egui::collapsing_header::CollapsingState;
+use egui::{RichText, Ui};
+use tracing::Level;
+
+use crate::tracing::LevelCounts;
+use crate::ui::color::ToColor32;
+use crate::ui::state::{is_within, TargetFilter};
+
+/// Targets seen by the collector as a tree split on `::`, with checkboxes to
+/// hide whole subtrees.
+#[derive(Default)]
+pub struct TargetTree<'a> {
+    state: Option<&'a mut TargetFilter>,
+    counts: BTreeMap<String, LevelCounts>,
+}
+
+#[derive(Default)]
+struct Node {
+    /// Events with exactly this target.
+    own: LevelCounts,
+    /// Events with this target or any descendant.
+    total: LevelCounts,
+    children: BTreeMap<String, Node>,
+}
+
+impl<'a> TargetTree<'a> {
+    pub fn state(mut self, v: &'a mut TargetFilter) -> Self {
+        self.state = Some(v);
+        self
+    }
+
+    pub fn counts(mut self, v: BTreeMap<String, LevelCounts>) -> Self {
+        self.counts = v;
+        self
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let state = self.state.unwrap();
+        let mut root = Node::default();
+        for (target, counts) in &self.counts {
+            let mut node = &mut root;
+            for segment in target.split("::") {
+                node.total.add(counts);
+                node = node.children.entry(segment.to_owned()).or_default();
+            }
+            node.total.add(counts);
+            node.own.add(counts);
+        }
+
+        if root.children.is_empty() {
+            ui.weak("No events yet");
+            return;
+        }
+        for (segment, node) in &root.children {
+            node_ui(ui, state, segment.clone(), segment, node, true);
+        }
+    }
+}
+
+fn node_ui(
+    ui: &mut Ui,
+    state: &mut TargetFilter,
+    path: String,
+    segment: &str,
+    node: &Node,
+    parent_visible: bool,
+) {
+    let id = ui.make_persistent_id(("target_tree", &path));
+    let header = |ui: &mut Ui, state: &mut TargetFilter| {
+        let mut visible = !state.hidden.contains(&path);
+        let checkbox = ui.add_enabled(
+            parent_visible,
+            egui::Checkbox::new(&mut visible, segment),
+        );
+        if checkbox.changed() {
+            if visible {
+                state.hidden.remove(&path);
+            } else {
+                // the hidden subtree replaces entries hidden inside it.
+                state.hidden.retain(|hidden| !is_within(hidden, &path));
+                state.hidden.insert(path.clone());
+            }
+        }
+        ui.weak(node.total.total().to_string())
+            .on_hover_ui(|ui| counts_ui(ui, &node.total));
+    };
+
+    let visible = parent_visible && !state.hidden.contains(&path);
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            // align leaves with the headers of collapsible nodes.
+            ui.add_space(ui.spacing().icon_width + ui.spacing().item_spacing.x);
+            header(ui, state);
+        });
+        return;
+    }
+    CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| header(ui, state))
+        .body(|ui| {
+            if node.own.total() > 0 {
+                ui.horizontal(|ui| {
+                    ui.weak(format!("{} events in {}", node.own.total(), path))
+                        .on_hover_ui(|ui| counts_ui(ui, &node.own));
+                });
+            }
+            for (segment, child) in &node.children {
+                let child_path = format!("{}::{}", path, segment);
+                node_ui(ui, state, child_path, segment, child, visible);
+            }
+        });
+}
+
+fn counts_ui(ui: &mut Ui, counts: &LevelCounts) {
+    for level in [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG, Level::TRACE] {
+        let count = counts.get(level);
+        if count > 0 {
+            ui.label(
+                RichText::new(format!("{}: {}", level.as_str(), count))
+                    .color(level.to_color32()),
+            );
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use std::io::Write;
+
+use crate::tracing::ExportFormat;
+
+/// Destination of events exported from the toolbar of a
+/// [`Logs`](super::Logs) widget, e.g. a file natively or a download in the
+/// browser. Without an exporter the events are copied to the clipboard.
+pub trait LogsExporter: Send + Sync {
+    /// Returns the writer for an export in the given format, or `None` to
+    /// cancel it. The writer is flushed once all events were written.
+    fn writer(&self, format: ExportFormat) -> Option<Box<dyn Write>>;
+}
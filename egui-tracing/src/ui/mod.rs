@@ -1,156 +1,477 @@
+mod cache;
 mod color;
 mod components;
+mod export;
+mod search;
+mod span_timeline;
 mod state;
+mod storage;
 
+use std::cell::Cell;
+use std::hash::Hash;
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
 
-use egui::{Label, Response, TextStyle, TextWrapMode, Widget};
-use globset::GlobSetBuilder;
+use egui::text::LayoutJob;
+use egui::{
+    Id,
+    Label,
+    Response,
+    RichText,
+    TextFormat,
+    TextStyle,
+    TextWrapMode,
+    Widget,
+};
 
-use self::color::ToColor32;
+use self::cache::LogsCache;
+use self::color::{source_color, ToColor32};
+use self::components::capture_menu_button::CaptureMenuButton;
+use self::components::column_menu_button::ColumnMenuButton;
 use self::components::common::CommonProps;
 use self::components::constants;
+use self::components::event_details::EventDetails;
+use self::components::export_menu_button::ExportMenuButton;
 use self::components::level_menu_button::LevelMenuButton;
+use self::components::query_bar::QueryBar;
+use self::components::search_box::SearchBox;
+use self::components::sort_button::SortButton;
+use self::components::source_menu_button::SourceMenuButton;
 use self::components::table::Table;
 use self::components::table_cell::TableCell;
 use self::components::table_header::TableHeader;
 use self::components::target_menu_button::TargetMenuButton;
-use self::state::LogsState;
-use crate::string::Ellipse;
+use self::search::Search;
+pub use self::export::LogsExporter;
+pub use self::span_timeline::SpanTimeline;
+use self::state::{Column, LogsState};
+pub use self::storage::LogsStorage;
+use self::storage::{load_state, save_state};
 use crate::time::DateTimeFormatExt;
 use crate::tracing::collector::EventCollector;
-use crate::tracing::CollectedEvent;
+use crate::tracing::span::format_span_path;
+use crate::tracing::{CollectedEvent, FieldValue};
 
 pub struct Logs {
     collector: EventCollector,
+    span_filter: Option<u64>,
+    id: Option<Id>,
+    storage: Option<Arc<dyn LogsStorage>>,
+    exporter: Option<Arc<dyn LogsExporter>>,
+    initial_query: Option<String>,
 }
 
 impl Logs {
     #[must_use]
     pub const fn new(collector: EventCollector) -> Self {
-        Self { collector }
+        Self {
+            collector,
+            span_filter: None,
+            id: None,
+            storage: None,
+            exporter: None,
+            initial_query: None,
+        }
+    }
+
+    /// Keys the view settings by `id_source` instead of the id of the parent
+    /// [`Ui`](egui::Ui), so that they are found again after restarts even if
+    /// the layout changes. Required when showing more than one view in the
+    /// same `Ui`, which would otherwise share their settings; views with
+    /// different ids are filtered independently. The settings are persisted
+    /// when the `persistence` feature is enabled and the app persists egui's
+    /// memory, e.g. with eframe.
+    pub fn id_source(mut self, id_source: impl Hash) -> Self {
+        self.id = Some(Id::new(id_source));
+        self
+    }
+
+    /// Additionally loads and saves the view settings with the given storage.
+    pub fn storage(mut self, storage: Arc<dyn LogsStorage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Writes the events exported from the toolbar with the given exporter
+    /// instead of copying them to the clipboard.
+    pub fn exporter(mut self, exporter: Arc<dyn LogsExporter>) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
+    /// Query applied when the view is shown for the first time, e.g.
+    /// `level>=warn` for an errors-only view, see
+    /// [`Query`](crate::tracing::Query). Settings restored from memory or
+    /// storage take precedence.
+    pub fn initial_query(mut self, query: impl Into<String>) -> Self {
+        self.initial_query = Some(query.into());
+        self
+    }
+
+    fn initial_state(&self) -> LogsState {
+        let mut state = LogsState::default();
+        if let Some(query) = &self.initial_query {
+            state.query.input = query.clone();
+            state.query.apply();
+        }
+        state
+    }
+
+    /// Only show events emitted inside the span with the given
+    /// [`SpanInfo::id`](crate::tracing::SpanInfo::id), e.g. the one selected
+    /// in a [`SpanTimeline`].
+    pub const fn span_filter(mut self, span: Option<u64>) -> Self {
+        self.span_filter = span;
+        self
     }
 }
 
 impl Widget for Logs {
     fn ui(self, ui: &mut egui::Ui) -> Response {
-        let state = ui.memory_mut(|mem| {
-            let state_mem_id = ui.id();
+        let id = self.id.unwrap_or_else(|| ui.id());
+        ui.push_id(id, |ui| self.show(ui, id)).inner
+    }
+}
+
+impl Logs {
+    fn show(self, ui: &mut egui::Ui, id: Id) -> Response {
+        let storage = self.storage.as_deref();
+        let mut state = load_state(ui, id, storage)
+            .unwrap_or_else(|| self.initial_state());
+
+        let cache = ui.memory_mut(|mem| {
+            let cache_mem_id = id.with("cache");
             mem.data
-                .get_temp_mut_or_insert_with(state_mem_id, || {
-                    Arc::new(Mutex::new(LogsState::default()))
+                .get_temp_mut_or_insert_with(cache_mem_id, || {
+                    Arc::new(Mutex::new(LogsCache::default()))
                 })
                 .clone()
         });
-        let mut state = state.lock().unwrap();
-
-        // TODO: cache the globset
-        let glob = {
-            let mut glob = GlobSetBuilder::new();
-            for target in state.target_filter.targets.clone() {
-                glob.add(target);
+        let mut cache = cache.lock().unwrap();
+        cache.update(&self.collector, &state, self.span_filter);
+        if !state.sources_seen && cache.sources().len() > 1 {
+            state.sources_seen = true;
+            if !state.columns.contains(&Column::Source) {
+                // next to the time, which the merged events are ordered by.
+                let index = state.columns.len().min(1);
+                state.columns.insert(index, Column::Source);
             }
-            glob.build().unwrap()
-        };
-
-        let events = self.collector.events();
-        let filtered_events = events
-            .iter()
-            .filter(|event| state.level_filter.get(event.level) && !glob.is_match(&event.target))
-            .collect::<Vec<_>>();
+        }
 
         let row_height = constants::SEPARATOR_SPACING
             + ui.style().text_styles.get(&TextStyle::Small).unwrap().size;
 
-        Table::default()
+        if let Some(event) = state.selection.cursor().cloned() {
+            egui::SidePanel::right(id.with("details"))
+                .resizable(true)
+                .default_width(320.0)
+                .show_inside(ui, |ui| {
+                    EventDetails::default()
+                        .event(&event)
+                        .selected(state.selection.items().len())
+                        .on_close(|| state.selection.clear())
+                        .show(ui);
+                });
+        }
+
+        let evicted = self.collector.evicted();
+        let new_events = if state.paused {
+            cache.new_events(&self.collector)
+        } else {
+            0
+        };
+        let search = cache.search();
+        let selected = state.selection.items().to_vec();
+        let columns = state.columns.clone();
+        // shared by the header, which resizes them, and the rows.
+        let widths: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                let width = state.column_widths.get(column).copied();
+                Cell::new(width.unwrap_or_else(|| column.default_width()))
+            })
+            .collect();
+        // the last column takes the remaining space.
+        let is_last = |index: usize| index + 1 == columns.len();
+
+        let response = Table::default()
             .on_clear(|| {
                 self.collector.clear();
             })
-            .header(|ui| {
-                TableHeader::default()
-                    .common_props(CommonProps::new().min_width(100.0))
-                    .children(|ui| {
-                        ui.label("Time");
-                    })
+            .toolbar(|ui| {
+                SearchBox::default()
+                    .state(&mut state.search)
+                    .error(cache.search_error())
                     .show(ui);
-                TableHeader::default()
-                    .common_props(CommonProps::new().min_width(80.0))
-                    .children(|ui| {
-                        LevelMenuButton::default()
-                            .state(&mut state.level_filter)
-                            .show(ui);
-                    })
+                ui.separator();
+                QueryBar::default().state(&mut state.query).show(ui);
+                ui.separator();
+                CaptureMenuButton::default()
+                    .handle(self.collector.handle())
+                    .state(&mut state.capture)
                     .show(ui);
-                TableHeader::default()
-                    .common_props(CommonProps::new().min_width(120.0))
-                    .children(|ui| {
-                        TargetMenuButton::default()
-                            .state(&mut state.target_filter)
-                            .show(ui);
-                    })
+                ExportMenuButton::default()
+                    .events(cache.filtered())
+                    .selected(&selected)
+                    .exporter(self.exporter.as_deref())
                     .show(ui);
-                TableHeader::default()
-                    .common_props(CommonProps::new().min_width(120.0))
-                    .children(|ui| {
-                        ui.label("Message");
-                    })
+                ColumnMenuButton::default()
+                    .state(&mut state.columns)
+                    .field_keys(cache.field_keys())
                     .show(ui);
+                if let Some(span_id) = self.span_filter {
+                    let name = cache
+                        .filtered()
+                        .first()
+                        .and_then(|event| event.spans.iter().find(|span| span.id == span_id))
+                        .map_or_else(|| format!("#{}", span_id), |span| span.name.clone());
+                    ui.label(format!("in span {}", name))
+                        .on_hover_text("Only events inside the selected span are shown");
+                }
+                if evicted > 0 {
+                    ui.weak(format!("{} evicted", evicted)).on_hover_text(
+                        "Events dropped because the collector reached its capacity",
+                    );
+                }
             })
-            .row_height(row_height)
-            .row(|ui, event: &CollectedEvent| {
-                TableCell::default()
-                    .common_props(CommonProps::new().min_width(100.0))
-                    .children(|ui| {
-                        ui.label(event.time.format_short())
-                            .on_hover_text(event.time.format_detailed());
-                    })
-                    .show(ui);
-                TableCell::default()
-                    .common_props(CommonProps::new().min_width(80.0))
-                    .children(|ui| {
-                        ui.colored_label(event.level.to_color32(), event.level.as_str());
-                    })
-                    .show(ui);
-                TableCell::default()
-                    .common_props(CommonProps::new().min_width(120.0))
-                    .children(|ui| {
-                        ui.label(event.target.truncate_graphemes(18))
-                            .on_hover_text(&event.target);
-                    })
-                    .show(ui);
-                TableCell::default()
-                    .common_props(CommonProps::new().min_width(120.0))
-                    .children(|ui| {
-                        let mut short_message = String::new();
-                        let mut complete_message = String::new();
-                        let mut log_message = String::new();
-
-                        if let Some(msg) = event.fields.get("message") {
-                            let msg = msg.trim();
-                            short_message.push_str(msg);
-                            complete_message.push_str(msg);
-                        }
-
-                        for (key, value) in &event.fields {
-                            if key == "message" {
-                                continue;
+            .header(|ui| {
+                for (index, column) in columns.iter().enumerate() {
+                    let header = if is_last(index) {
+                        let props = CommonProps::new().min_width(column.default_width());
+                        TableHeader::default().common_props(props)
+                    } else {
+                        TableHeader::default().width(&widths[index])
+                    };
+                    header
+                        .children(|ui| {
+                            match column {
+                                Column::Level => {
+                                    LevelMenuButton::default()
+                                        .state(&mut state.level_filter)
+                                        .show(ui);
+                                }
+                                Column::Target => {
+                                    TargetMenuButton::default()
+                                        .state(&mut state.target_filter)
+                                        .collector(&self.collector)
+                                        .show(ui);
+                                }
+                                Column::Source => {
+                                    SourceMenuButton::default()
+                                        .state(&mut state.source_filter)
+                                        .sources(cache.sources())
+                                        .show(ui);
+                                }
+                                _ => {
+                                    ui.label(column.label());
+                                }
                             }
-                            if key.starts_with("log.") {
-                                log_message.push_str(&format!("\n {}: {}", key, value));
-                            } else {
-                                short_message.push_str(&format!(", {}: {}", key, value));
-                                complete_message.push_str(&format!("\n {}: {}", key, value));
+                            if column.is_sortable() {
+                                SortButton::default()
+                                    .state(&mut state.sort)
+                                    .column(column.clone())
+                                    .show(ui);
                             }
-                        }
+                        })
+                        .show(ui);
+                }
+            })
+            .row_height(row_height)
+            .selection(&mut state.selection, |event| Arc::as_ptr(event) as usize)
+            .pause(&mut state.paused, new_events)
+            .row(|ui, event: &Arc<CollectedEvent>| {
+                for (index, column) in columns.iter().enumerate() {
+                    let cell = if is_last(index) {
+                        let props = CommonProps::new().min_width(column.default_width());
+                        TableCell::default().common_props(props)
+                    } else {
+                        TableCell::default().width(widths[index].get())
+                    };
+                    cell.children(|ui| cell_ui(ui, column, event, search))
+                        .show(ui);
+                }
+            })
+            .show(ui, cache.filtered());
 
-                        complete_message.push_str("\n\n");
-                        complete_message.push_str(&log_message);
+        for (column, width) in columns.iter().zip(widths) {
+            let width = width.get();
+            if width == column.default_width() {
+                state.column_widths.remove(column);
+            } else {
+                state.column_widths.insert(column.clone(), width);
+            }
+        }
 
-                        ui.add(Label::new(short_message).wrap_mode(TextWrapMode::Extend))
-                            .on_hover_text(complete_message);
-                    })
-                    .show(ui);
-            })
-            .show(ui, filtered_events.iter())
+        save_state(ui, id, state, storage);
+        response
+    }
+}
+
+fn cell_ui(
+    ui: &mut egui::Ui,
+    column: &Column,
+    event: &CollectedEvent,
+    search: Option<&Search>,
+) {
+    match column {
+        Column::Time => {
+            ui.label(event.time.format_short())
+                .on_hover_text(event.time.format_detailed());
+        }
+        Column::Level => {
+            ui.colored_label(event.level.to_color32(), event.level.as_str());
+        }
+        Column::Target => {
+            ui.add(Label::new(event.target.as_str()).truncate())
+                .on_hover_text(&event.target);
+        }
+        Column::Span => {
+            let span_path = format_span_path(&event.spans, false);
+            ui.add(Label::new(span_path.as_str()).truncate())
+                .on_hover_text(format_span_path(&event.spans, true));
+        }
+        Column::Thread => {
+            let thread = event.thread().unwrap_or_default().to_owned();
+            ui.add(Label::new(thread.as_str()).truncate())
+                .on_hover_text(thread);
+        }
+        Column::ModulePath => {
+            let module_path = event.module_path.clone().unwrap_or_default();
+            ui.add(Label::new(module_path.as_str()).truncate())
+                .on_hover_text(module_path);
+        }
+        Column::File => {
+            let location = event.location().unwrap_or_default();
+            ui.add(Label::new(location.as_str()).truncate())
+                .on_hover_text(location);
+        }
+        Column::Source => {
+            let source = event.source.as_deref();
+            let mut text = RichText::new(source_label(source));
+            if let Some(color) = source_color(source) {
+                text = text.color(color);
+            }
+            ui.add(Label::new(text).truncate())
+                .on_hover_text(source_label(source));
+        }
+        Column::Message => message_ui(ui, event, search),
+        Column::Field(key) => {
+            let value = event.fields.get(key).map(ToString::to_string);
+            let value = value.unwrap_or_default();
+            ui.add(Label::new(value.as_str()).truncate())
+                .on_hover_text(value);
+        }
+    }
+}
+
+/// Name shown for an event source, see [`CollectedEvent::source`].
+fn source_label(source: Option<&str>) -> &str {
+    source.unwrap_or("local")
+}
+
+/// The single-line message shown in the message column, which is also what
+/// the search matches against.
+fn short_message(event: &CollectedEvent) -> String {
+    let mut short_message = String::new();
+    if let Some(msg) = event.fields.get("message") {
+        short_message.push_str(msg.to_string().trim());
+    }
+    for (key, value) in &event.fields {
+        if key != "message" && !key.starts_with("log.") {
+            short_message.push_str(&format!(", {}: {}", key, value));
+        }
+    }
+    short_message
+}
+
+fn message_ui(
+    ui: &mut egui::Ui,
+    event: &CollectedEvent,
+    search: Option<&Search>,
+) {
+    let mut complete_message = String::new();
+    let mut log_message = String::new();
+
+    if let Some(msg) = event.fields.get("message") {
+        complete_message.push_str(msg.to_string().trim());
+    }
+
+    for (key, value) in &event.fields {
+        if key == "message" {
+            continue;
+        }
+        if key.starts_with("log.") {
+            log_message.push_str(&format!("\n {}: {}", key, value));
+        } else {
+            complete_message.push_str(&format!("\n {}: {}", key, value));
+            if let FieldValue::Error { sources, .. } = value {
+                for source in sources {
+                    complete_message.push_str(&format!("\n   caused by: {}", source));
+                }
+            }
+        }
+    }
+
+    if !event.spans.is_empty() {
+        complete_message.push_str("\n\nspans:");
+        for span in &event.spans {
+            complete_message.push_str(&format!(
+                "\n {}",
+                format_span_path(std::slice::from_ref(span), true)
+            ));
+        }
+    }
+
+    complete_message.push('\n');
+    if let Some(location) = event.location() {
+        complete_message.push_str(&format!("\n {}", location));
+    }
+    if let Some(module_path) = &event.module_path {
+        complete_message.push_str(&format!("\n module: {}", module_path));
+    }
+    if let Some(thread) = event.thread() {
+        complete_message.push_str(&format!("\n thread: {}", thread));
+    }
+
+    complete_message.push('\n');
+    complete_message.push_str(&log_message);
+
+    let short_message = short_message(event);
+    let ranges = search.map(|search| search.find_ranges(&short_message));
+    let label = match ranges {
+        Some(ranges) if !ranges.is_empty() => {
+            Label::new(highlight(ui, &short_message, &ranges))
+        }
+        _ => Label::new(short_message),
+    };
+    ui.add(label.wrap_mode(TextWrapMode::Extend))
+        .on_hover_text(complete_message);
+}
+
+/// Lays out `text` with the given byte ranges highlighted.
+fn highlight(
+    ui: &egui::Ui,
+    text: &str,
+    ranges: &[Range<usize>],
+) -> LayoutJob {
+    let normal = TextFormat {
+        font_id: TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlighted = TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        color: ui.visuals().strong_text_color(),
+        ..normal.clone()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut end = 0;
+    for range in ranges {
+        job.append(&text[end..range.start], 0.0, normal.clone());
+        job.append(&text[range.clone()], 0.0, highlighted.clone());
+        end = range.end;
     }
+    job.append(&text[end..], 0.0, normal);
+    job
 }
@@ -0,0 +1,42 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use super::state::SearchState;
+
+/// A compiled search, matching plain text or a regular expression.
+#[derive(Debug, Clone)]
+pub struct Search {
+    regex: Regex,
+}
+
+impl Search {
+    /// Compiles the search, `None` if the input is empty.
+    pub fn new(state: &SearchState) -> Result<Option<Self>, regex::Error> {
+        if state.input.is_empty() {
+            return Ok(None);
+        }
+        let pattern = if state.regex {
+            state.input.clone()
+        } else {
+            regex::escape(&state.input)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!state.case_sensitive)
+            .build()?;
+        Ok(Some(Self { regex }))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Byte ranges of the non-empty matches in `text`.
+    pub fn find_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|m| m.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local};
+use egui::{pos2, vec2, Align2, Rect, Response, RichText, Sense, Stroke, TextStyle, Ui, Widget};
+
+use super::color::ToColor32;
+use crate::time::DateTimeFormatExt;
+use crate::tracing::span::{format_span_path, SpanRecord};
+use crate::tracing::EventCollector;
+
+const ROW_HEIGHT: f32 = 18.0;
+
+/// Waterfall of the spans recorded by an [`EventCollector`], one lane per
+/// thread. Clicking a span selects it, e.g. to pass it to
+/// [`Logs::span_filter`](super::Logs::span_filter).
+pub struct SpanTimeline<'a> {
+    collector: EventCollector,
+    selected: Option<&'a mut Option<u64>>,
+}
+
+impl<'a> SpanTimeline<'a> {
+    #[must_use]
+    pub const fn new(collector: EventCollector) -> Self {
+        Self {
+            collector,
+            selected: None,
+        }
+    }
+
+    /// Span selected by clicking, identified by [`SpanInfo::id`]. Clicking the
+    /// selected span again clears the selection.
+    ///
+    /// [`SpanInfo::id`]: crate::tracing::SpanInfo::id
+    pub fn selected(mut self, v: &'a mut Option<u64>) -> Self {
+        self.selected = Some(v);
+        self
+    }
+}
+
+impl Widget for SpanTimeline<'_> {
+    fn ui(mut self, ui: &mut Ui) -> Response {
+        let now = Local::now();
+        let spans = self.collector.spans();
+
+        ui.vertical(|ui| {
+            let (Some(start), Some(end)) = (
+                spans.iter().map(|span| span.created).min(),
+                spans.iter().map(|span| span.end(now)).max(),
+            ) else {
+                ui.weak("No spans recorded");
+                return;
+            };
+            let total = (end - start).num_microseconds().unwrap_or(i64::MAX).max(1) as f32;
+
+            let depths = span_depths(&spans);
+            let mut lanes: BTreeMap<&str, Vec<&SpanRecord>> = BTreeMap::new();
+            for span in &spans {
+                lanes.entry(span.lane()).or_default().push(span);
+            }
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    for (lane, records) in lanes {
+                        ui.label(RichText::new(lane).strong());
+
+                        let rows = records
+                            .iter()
+                            .map(|record| depths[&record.info.id] + 1)
+                            .max()
+                            .unwrap_or(1);
+                        let (response, painter) = ui.allocate_painter(
+                            vec2(ui.available_width(), rows as f32 * ROW_HEIGHT),
+                            Sense::hover(),
+                        );
+                        let rect = response.rect;
+                        let x = |time: DateTime<Local>| {
+                            let offset = (time - start).num_microseconds().unwrap_or(0) as f32;
+                            rect.left() + offset / total * rect.width()
+                        };
+
+                        for record in records {
+                            let top = rect.top() + depths[&record.info.id] as f32 * ROW_HEIGHT;
+                            let bar = Rect::from_min_max(
+                                pos2(x(record.created), top + 1.0),
+                                pos2(
+                                    x(record.end(now)).max(x(record.created) + 1.0),
+                                    top + ROW_HEIGHT - 1.0,
+                                ),
+                            );
+                            let color = record.info.level.to_color32();
+
+                            painter.rect_filled(bar, 2.0, color.gamma_multiply(0.25));
+                            for entry in &record.entries {
+                                let entered = x(entry.entered);
+                                let exited = x(entry.exited.unwrap_or(now)).max(entered + 1.0);
+                                painter.rect_filled(
+                                    Rect::from_x_y_ranges(entered..=exited, bar.y_range()),
+                                    0.0,
+                                    color.gamma_multiply(0.8),
+                                );
+                            }
+
+                            let selected = self
+                                .selected
+                                .as_deref()
+                                .is_some_and(|selected| *selected == Some(record.info.id));
+                            if selected {
+                                painter.rect_stroke(
+                                    bar,
+                                    2.0,
+                                    Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                                );
+                            }
+
+                            painter.with_clip_rect(bar.intersect(rect)).text(
+                                bar.left_center() + vec2(4.0, 0.0),
+                                Align2::LEFT_CENTER,
+                                &record.info.name,
+                                TextStyle::Small.resolve(ui.style()),
+                                ui.visuals().strong_text_color(),
+                            );
+
+                            let response = ui
+                                .interact(bar, ui.id().with(record.info.id), Sense::click())
+                                .on_hover_ui_at_pointer(|ui| span_tooltip(ui, record, now));
+                            if response.clicked() {
+                                if let Some(selected) = self.selected.as_deref_mut() {
+                                    *selected = if selected == &Some(record.info.id) {
+                                        None
+                                    } else {
+                                        Some(record.info.id)
+                                    };
+                                }
+                            }
+                        }
+
+                        ui.add_space(4.0);
+                    }
+                });
+        })
+        .response
+    }
+}
+
+fn span_tooltip(ui: &mut Ui, record: &SpanRecord, now: DateTime<Local>) {
+    ui.label(RichText::new(format_span_path(std::slice::from_ref(&record.info), true)).strong());
+    ui.label(format!("{} {}", record.info.level, record.info.target));
+    ui.label(format!("created: {}", record.created.format_detailed()));
+    match record.closed {
+        Some(closed) => ui.label(format!("closed: {}", closed.format_detailed())),
+        None => ui.label("closed: still open"),
+    };
+    ui.label(format!(
+        "busy: {:?}, idle: {:?}, entered {} times",
+        record.busy(now),
+        record.idle(now),
+        record.entries.len()
+    ));
+}
+
+/// Nesting depth of every span, counting only ancestors that are still
+/// recorded.
+fn span_depths(spans: &[SpanRecord]) -> HashMap<u64, usize> {
+    let parents = spans
+        .iter()
+        .map(|span| (span.info.id, span.parent))
+        .collect::<HashMap<_, _>>();
+
+    parents
+        .keys()
+        .map(|&id| {
+            let mut depth = 0;
+            let mut current = parents[&id];
+            while let Some(parent) = current.and_then(|parent| parents.get(&parent)) {
+                depth += 1;
+                current = *parent;
+            }
+            (id, depth)
+        })
+        .collect()
+}
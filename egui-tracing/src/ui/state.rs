@@ -1,14 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
 use globset::Glob;
 use serde::{Deserialize, Serialize};
 use tracing::Level;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+use super::components::table::Selection;
+use crate::tracing::query::{ParseQueryError, Query};
+use crate::tracing::CollectedEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LogsState {
     pub level_filter: LevelFilter,
     pub target_filter: TargetFilter,
+    pub source_filter: SourceFilter,
+    /// Whether events of more than one source were seen, after which the
+    /// source column is shown once.
+    pub sources_seen: bool,
+    /// Visible columns, in display order.
+    pub columns: Vec<Column>,
+    /// Widths of columns resized by dragging their header.
+    pub column_widths: BTreeMap<Column, f32>,
+    pub sort: Option<Sort>,
+    pub search: SearchState,
+    pub query: QueryState,
+    /// Edits to the collector's capture settings, which live in the
+    /// collector itself.
+    #[serde(skip)]
+    pub capture: CaptureState,
+    #[serde(skip)]
+    pub selection: Selection<Arc<CollectedEvent>>,
+    /// Whether the shown events are frozen while the collector keeps
+    /// collecting.
+    #[serde(skip)]
+    pub paused: bool,
+}
+
+impl Default for LogsState {
+    fn default() -> Self {
+        Self {
+            level_filter: LevelFilter::default(),
+            target_filter: TargetFilter::default(),
+            source_filter: SourceFilter::default(),
+            sources_seen: false,
+            columns: vec![
+                Column::Time,
+                Column::Level,
+                Column::Target,
+                Column::Span,
+                Column::Message,
+            ],
+            column_widths: BTreeMap::new(),
+            sort: None,
+            search: SearchState::default(),
+            query: QueryState::default(),
+            capture: CaptureState::default(),
+            selection: Selection::default(),
+            paused: false,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A column of the events table. Serialized as a string, so that it can be
+/// used as a map key in JSON.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(into = "String", try_from = "String")]
+pub enum Column {
+    Time,
+    Level,
+    Target,
+    Span,
+    Thread,
+    ModulePath,
+    File,
+    Source,
+    Message,
+    /// The value of a structured field, by key.
+    Field(String),
+}
+
+impl Column {
+    const FIELD_PREFIX: &'static str = "field:";
+
+    /// The built-in columns.
+    pub const ALL: [Column; 9] = [
+        Column::Time,
+        Column::Level,
+        Column::Target,
+        Column::Span,
+        Column::Thread,
+        Column::ModulePath,
+        Column::File,
+        Column::Source,
+        Column::Message,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            Column::Time => "Time",
+            Column::Level => "Level",
+            Column::Target => "Target",
+            Column::Span => "Span",
+            Column::Thread => "Thread",
+            Column::ModulePath => "Module",
+            Column::File => "File",
+            Column::Source => "Source",
+            Column::Message => "Message",
+            Column::Field(key) => key,
+        }
+    }
+
+    pub fn default_width(&self) -> f32 {
+        match self {
+            Column::Time => 100.0,
+            Column::Level => 80.0,
+            Column::Thread | Column::Source | Column::Field(_) => 100.0,
+            Column::File => 140.0,
+            Column::Target
+            | Column::Span
+            | Column::ModulePath
+            | Column::Message => 120.0,
+        }
+    }
+
+    pub fn is_sortable(&self) -> bool {
+        matches!(
+            self,
+            Column::Time
+                | Column::Level
+                | Column::Target
+                | Column::Source
+                | Column::Message
+                | Column::Field(_)
+        )
+    }
+}
+
+impl From<Column> for String {
+    fn from(column: Column) -> Self {
+        match column {
+            Column::ModulePath => "ModulePath".to_owned(),
+            Column::Field(key) => format!("{}{}", Column::FIELD_PREFIX, key),
+            column => column.label().to_owned(),
+        }
+    }
+}
+
+impl TryFrom<String> for Column {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if let Some(key) = name.strip_prefix(Column::FIELD_PREFIX) {
+            return Ok(Column::Field(key.to_owned()));
+        }
+        Column::ALL
+            .into_iter()
+            .find(|column| String::from(column.clone()) == name)
+            .ok_or_else(|| format!("unknown column `{}`", name))
+    }
+}
+
+/// Order of the shown events. Events are in time order when not sorted, so
+/// that the events of several sources are merged, and in the order they were
+/// collected for equal times.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sort {
+    pub column: Column,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LevelFilter {
     pub trace: bool,
     pub debug: bool,
@@ -17,10 +181,122 @@ pub struct LevelFilter {
     pub error: bool,
 }
 
+/// Target globs deciding which events are shown. Events matching an enabled
+/// exclude entry are hidden; otherwise, if there are enabled include
+/// entries, only events matching one of them are shown.
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Hash)]
+#[serde(default)]
 pub struct TargetFilter {
     pub input: String,
-    pub targets: Vec<Glob>,
+    /// Mode of the next added entry.
+    pub mode: TargetMode,
+    pub targets: Vec<TargetEntry>,
+    /// Target paths hidden in the target tree, together with their
+    /// descendants.
+    pub hidden: BTreeSet<String>,
+}
+
+/// Sources whose events are hidden, see
+/// [`CollectedEvent::source`](crate::tracing::CollectedEvent::source).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceFilter {
+    /// `None` stands for the events of this process.
+    pub hidden: BTreeSet<Option<String>>,
+}
+
+impl SourceFilter {
+    pub fn get(&self, source: Option<&str>) -> bool {
+        !self.hidden.contains(&source.map(str::to_owned))
+    }
+}
+
+/// Whether `target` is `path` or one of its descendants.
+pub fn is_within(target: &str, path: &str) -> bool {
+    target
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct TargetEntry {
+    pub glob: Glob,
+    pub mode: TargetMode,
+    /// Disabled entries are kept in the menu but ignored.
+    pub enabled: bool,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub enum TargetMode {
+    Include,
+    #[default]
+    Exclude,
+}
+
+impl TargetMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetMode::Include => "Include",
+            TargetMode::Exclude => "Exclude",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchState {
+    pub input: String,
+    pub case_sensitive: bool,
+    /// Treat the input as a regular expression instead of plain text.
+    pub regex: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryState {
+    pub input: String,
+    /// The query events are filtered with, only updated with valid queries.
+    pub applied: String,
+    /// Previously applied queries, most recent last.
+    pub history: Vec<String>,
+    #[serde(skip)]
+    pub error: Option<ParseQueryError>,
+}
+
+impl QueryState {
+    const MAX_HISTORY: usize = 20;
+
+    /// Parses the input and applies it if valid. An empty input removes the
+    /// query.
+    pub fn apply(&mut self) {
+        let input = self.input.trim();
+        if input.is_empty() {
+            self.applied.clear();
+            self.error = None;
+            return;
+        }
+        match input.parse::<Query>() {
+            Ok(_) => {
+                self.applied = input.to_owned();
+                self.error = None;
+                self.history.retain(|query| query != input);
+                self.history.push(input.to_owned());
+                if self.history.len() > Self::MAX_HISTORY {
+                    self.history.remove(0);
+                }
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CaptureState {
+    /// Directives being edited, `None` until the capture menu is opened.
+    pub directives_input: Option<String>,
+    pub directives_error: Option<String>,
 }
 
 impl Default for LevelFilter {
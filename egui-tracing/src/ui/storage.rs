@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use egui::{Id, Ui};
+
+use super::state::LogsState;
+
+/// Stores the view settings of a [`Logs`](super::Logs) widget outside of
+/// egui's memory, e.g. in an application's own config file. Values are JSON
+/// strings.
+pub trait LogsStorage: Send + Sync {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&self, key: &str, value: &str);
+}
+
+/// How often the state is compared with the one last given to a
+/// [`LogsStorage`], which means serializing it.
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+fn storage_key(id: Id) -> String {
+    format!("egui_tracing_logs_{:016x}", id.value())
+}
+
+/// Loads the state from egui's memory, falling back to `storage` the first
+/// time the widget is shown. `None` if neither has a state for `id`.
+pub fn load_state(
+    ui: &Ui,
+    id: Id,
+    storage: Option<&dyn LogsStorage>,
+) -> Option<LogsState> {
+    #[cfg(feature = "persistence")]
+    let state = ui.data_mut(|data| data.get_persisted::<LogsState>(id));
+    #[cfg(not(feature = "persistence"))]
+    let state = ui.data(|data| data.get_temp::<LogsState>(id));
+
+    state.or_else(|| {
+        let value = storage?.load(&storage_key(id))?;
+        serde_json::from_str(&value).ok()
+    })
+}
+
+/// Stores the state in egui's memory, and in `storage` when it changed,
+/// checked at most every [`SAVE_INTERVAL`].
+pub fn save_state(
+    ui: &Ui,
+    id: Id,
+    state: LogsState,
+    storage: Option<&dyn LogsStorage>,
+) {
+    if let Some(storage) = storage {
+        let checked_id = id.with("checked");
+        let now = ui.input(|i| i.time);
+        let checked = ui.data(|data| data.get_temp::<f64>(checked_id));
+        let due = checked.map(|checked| checked + SAVE_INTERVAL.as_secs_f64());
+        match due {
+            // repaint once more, in case this frame's changes are the last.
+            Some(due) if due > now => {
+                ui.ctx().request_repaint_after_secs((due - now) as f32);
+            }
+            _ => {
+                let saved_id = id.with("saved");
+                let value = serde_json::to_string(&state).unwrap_or_default();
+                let saved = ui.data(|data| data.get_temp::<String>(saved_id));
+                if saved.as_ref() != Some(&value) {
+                    storage.save(&storage_key(id), &value);
+                    ui.data_mut(|data| data.insert_temp(saved_id, value));
+                }
+                ui.data_mut(|data| data.insert_temp(checked_id, now));
+            }
+        }
+    }
+
+    #[cfg(feature = "persistence")]
+    ui.data_mut(|data| data.insert_persisted(id, state));
+    #[cfg(not(feature = "persistence"))]
+    ui.data_mut(|data| data.insert_temp(id, state));
+}
@@ -1,6 +1,13 @@
+#[cfg(target_arch = "wasm32")]
+use std::io::{self, Write};
+#[cfg(target_arch = "wasm32")]
+use std::sync::Arc;
+
 use egui_tracing::egui;
 use egui_tracing::tracing::collector::EventCollector;
 #[cfg(target_arch = "wasm32")]
+use egui_tracing::tracing::ExportFormat;
+#[cfg(target_arch = "wasm32")]
 use egui_tracing::tracing_subscriber;
 #[cfg(target_arch = "wasm32")]
 use egui_tracing::tracing_subscriber::layer::SubscriberExt;
@@ -37,6 +44,61 @@ pub fn start() {
     });
 }
 
+/// Offers exported events as a download.
+#[cfg(target_arch = "wasm32")]
+struct DownloadExporter;
+
+#[cfg(target_arch = "wasm32")]
+impl egui_tracing::LogsExporter for DownloadExporter {
+    fn writer(&self, format: ExportFormat) -> Option<Box<dyn Write>> {
+        Some(Box::new(Download {
+            format,
+            buffer: Vec::new(),
+        }))
+    }
+}
+
+/// Collects the exported events and downloads them when flushed.
+#[cfg(target_arch = "wasm32")]
+struct Download {
+    format: ExportFormat,
+    buffer: Vec<u8>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Write for Download {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.download()
+            .map_err(|err| io::Error::other(format!("{:?}", err)))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Download {
+    fn download(&self) -> Result<(), JsValue> {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(&self.buffer[..]));
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(self.format.mime_type());
+        let blob =
+            web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let document = web_sys::window().unwrap().document().unwrap();
+        let anchor = document
+            .create_element("a")?
+            .dyn_into::<web_sys::HtmlAnchorElement>()?;
+        anchor.set_href(&url);
+        anchor.set_download(&format!("events.{}", self.format.extension()));
+        anchor.click();
+        web_sys::Url::revoke_object_url(&url)
+    }
+}
+
 pub struct MyApp {
     collector: EventCollector,
 }
@@ -51,7 +113,10 @@ impl MyApp {
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add(egui_tracing::Logs::new(self.collector.clone()))
+            let logs = egui_tracing::Logs::new(self.collector.clone());
+            #[cfg(target_arch = "wasm32")]
+            let logs = logs.exporter(Arc::new(DownloadExporter));
+            ui.add(logs)
         });
     }
 }
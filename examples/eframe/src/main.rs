@@ -1,4 +1,9 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+
 use egui_tracing::tracing::collector::EventCollector;
+use egui_tracing::tracing::ExportFormat;
 use egui_tracing::tracing_subscriber::layer::SubscriberExt;
 use egui_tracing::tracing_subscriber::util::SubscriberInitExt;
 use egui_tracing::{egui, tracing_subscriber};
@@ -25,20 +30,57 @@ fn main() {
     .unwrap();
 }
 
+/// Saves exported events to `events.<extension>` in the working directory.
+struct FileExporter;
+
+impl egui_tracing::LogsExporter for FileExporter {
+    fn writer(&self, format: ExportFormat) -> Option<Box<dyn Write>> {
+        let file = File::create(format!("events.{}", format.extension())).ok()?;
+        Some(Box::new(BufWriter::new(file)))
+    }
+}
+
 pub struct MyApp {
     collector: EventCollector,
+    selected_span: Option<u64>,
 }
 
 impl MyApp {
     fn new(collector: EventCollector) -> Self {
-        Self { collector }
+        Self {
+            collector,
+            selected_span: None,
+        }
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::bottom("spans")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui_tracing::SpanTimeline::new(self.collector.clone())
+                        .selected(&mut self.selected_span),
+                )
+            });
+        egui::SidePanel::right("warnings")
+            .resizable(true)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui_tracing::Logs::new(self.collector.clone())
+                        .id_source("warnings")
+                        .initial_query("level>=warn"),
+                )
+            });
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add(egui_tracing::Logs::new(self.collector.clone()))
+            ui.add(
+                egui_tracing::Logs::new(self.collector.clone())
+                    .id_source("logs")
+                    .exporter(Arc::new(FileExporter))
+                    .span_filter(self.selected_span),
+            )
         });
     }
 }